@@ -0,0 +1,178 @@
+use {
+    crate::error::VaultError,
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        account_info::AccountInfo, ed25519_program, instruction::Instruction, msg,
+        program_error::ProgramError, pubkey::Pubkey,
+        sysvar::instructions::get_instruction_relative,
+    },
+};
+
+/// Length of an ed25519 signature.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Domain prefix of every approval message, so an owner signature over one
+/// can never pass for a signature over anything else.
+pub const APPROVAL_DOMAIN: &[u8] = b"fauxstodian:owner-approval:v1";
+
+// Layout of the native ed25519 program instruction data: the signature count
+// and a padding byte, followed by one set of offsets per signature.
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const DATA_START: usize = OFFSETS_START + OFFSETS_LEN;
+// Instruction index of data held by the ed25519 instruction itself.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Vault instructions an owner may approve off-chain.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum ApprovalAction {
+    /// `VaultInstruction::CloseAccount`, for the whole balance (amount zero)
+    CloseAccount,
+    /// `VaultInstruction::Withdraw`
+    Withdraw,
+    /// `VaultInstruction::WithdrawTo`
+    WithdrawTo,
+}
+
+/// Canonical message an owner signs with ed25519 to approve a vault
+/// instruction without signing the transaction.
+///
+/// The signature is checked by the native ed25519 program, in the instruction
+/// right before the approved one. Each approval carries a nonce above the last
+/// one the vault accepted, so it cannot be replayed.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct OwnerApproval {
+    /// The vault program
+    pub program_id: Pubkey,
+    /// The vault record address
+    pub vault: Pubkey,
+    /// The approved instruction
+    pub action: ApprovalAction,
+    /// The account paid out to: the destination of `WithdrawTo`, the owner
+    /// of `Withdraw` and `CloseAccount`
+    pub destination: Pubkey,
+    /// Lamports the instruction pays out
+    pub amount: u64,
+    /// Replay protection nonce
+    pub nonce: u64,
+}
+
+impl OwnerApproval {
+    /// The message to sign: `APPROVAL_DOMAIN` followed by the Borsh-encoded
+    /// approval.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = APPROVAL_DOMAIN.to_vec();
+        message.extend_from_slice(&self.try_to_vec().unwrap());
+        message
+    }
+
+    /// Create a native ed25519 program instruction verifying the owner
+    /// signature over the approval.
+    pub fn verify_instruction(
+        &self,
+        owner: &Pubkey,
+        signature: &[u8; SIGNATURE_LEN],
+    ) -> Instruction {
+        let message = self.message();
+        let public_key_offset = DATA_START;
+        let signature_offset = public_key_offset + owner.as_ref().len();
+        let message_offset = signature_offset + SIGNATURE_LEN;
+
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset as u16,
+            CURRENT_INSTRUCTION,
+            public_key_offset as u16,
+            CURRENT_INSTRUCTION,
+            message_offset as u16,
+            message.len() as u16,
+            CURRENT_INSTRUCTION,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(signature);
+        data.extend_from_slice(&message);
+
+        Instruction {
+            program_id: ed25519_program::id(),
+            accounts: vec![],
+            data,
+        }
+    }
+
+    /// Load the approval signed by an owner from the ed25519 instruction
+    /// preceding the current one, per the instructions sysvar.
+    pub fn load(instructions: &AccountInfo, owner: &Pubkey) -> Result<Self, ProgramError> {
+        let instruction = get_instruction_relative(-1, instructions)
+            .map_err(|_| ProgramError::from(VaultError::InvalidApproval))?;
+        if instruction.program_id != ed25519_program::id() {
+            msg!("Owner approval must follow an ed25519 instruction");
+            return Err(VaultError::InvalidApproval.into());
+        }
+        Self::unpack_verified(&instruction.data, owner).ok_or_else(|| {
+            msg!("No owner approval in ed25519 instruction");
+            VaultError::InvalidApproval.into()
+        })
+    }
+
+    // Decode the approval from ed25519 instruction data holding a single
+    // signature by the owner over its own data.
+    fn unpack_verified(data: &[u8], owner: &Pubkey) -> Option<Self> {
+        if data.first() != Some(&1) {
+            return None;
+        }
+        let offsets = data.get(OFFSETS_START..DATA_START)?;
+        let field = |i: usize| u16::from_le_bytes([offsets[2 * i], offsets[2 * i + 1]]);
+        if [field(1), field(3), field(6)] != [CURRENT_INSTRUCTION; 3] {
+            return None;
+        }
+        let public_key_offset = usize::from(field(2));
+        let message_offset = usize::from(field(4));
+        let message_len = usize::from(field(5));
+
+        let public_key = data.get(public_key_offset..public_key_offset + owner.as_ref().len())?;
+        if public_key != owner.as_ref() {
+            return None;
+        }
+        let message = data.get(message_offset..message_offset + message_len)?;
+        Self::try_from_slice(message.strip_prefix(APPROVAL_DOMAIN)?).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_instruction_round_trip() {
+        let owner = Pubkey::new_from_array([99; 32]);
+        let approval = OwnerApproval {
+            program_id: Pubkey::new_from_array([1; 32]),
+            vault: Pubkey::new_from_array([7; 32]),
+            action: ApprovalAction::Withdraw,
+            destination: owner,
+            amount: 1_000,
+            nonce: 3,
+        };
+        let mut expected = APPROVAL_DOMAIN.to_vec();
+        expected.extend_from_slice(approval.program_id.as_ref());
+        expected.extend_from_slice(approval.vault.as_ref());
+        expected.push(1);
+        expected.extend_from_slice(owner.as_ref());
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        assert_eq!(approval.message(), expected);
+
+        let instruction = approval.verify_instruction(&owner, &[5; SIGNATURE_LEN]);
+        assert_eq!(instruction.program_id, ed25519_program::id());
+        assert_eq!(
+            OwnerApproval::unpack_verified(&instruction.data, &owner),
+            Some(approval)
+        );
+        assert_eq!(
+            OwnerApproval::unpack_verified(&instruction.data, &Pubkey::new_unique()),
+            None
+        );
+    }
+}
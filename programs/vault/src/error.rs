@@ -12,6 +12,150 @@ pub enum VaultError {
     /// Calculation overflow.
     #[error("Calculation overflow")]
     Overflow,
+
+    /// Insufficient funds for the requested withdrawal.
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+
+    /// The securities intermediary is not recognized by the program config.
+    #[error("Unknown intermediary")]
+    UnknownIntermediary,
+
+    /// Invalid fee schedule.
+    #[error("Invalid fee schedule")]
+    InvalidFeeSchedule,
+
+    /// The vault has no pending ownership proposal.
+    #[error("No pending owner")]
+    NoPendingOwner,
+
+    /// The vault has been closed and can no longer be used.
+    #[error("Vault closed")]
+    VaultClosed,
+
+    /// The vault record was written by an older program version.
+    #[error("Vault record requires migration")]
+    OutdatedRecord,
+
+    /// The vault is frozen, or the program is paused.
+    #[error("Vault frozen")]
+    Frozen,
+
+    /// The vault is time-locked.
+    #[error("Vault locked")]
+    Locked,
+
+    /// The withdrawal exceeds the delegate's remaining allowance.
+    #[error("Allowance exceeded")]
+    AllowanceExceeded,
+
+    /// The delegate's allowance has expired.
+    #[error("Allowance expired")]
+    AllowanceExpired,
+
+    /// The withdrawal exceeds the vault's limit for the current window.
+    #[error("Withdrawal limit exceeded")]
+    WithdrawalLimitExceeded,
+
+    /// The withdrawal destination is not allowlisted for the vault.
+    #[error("Destination not allowlisted")]
+    DestinationNotAllowed,
+
+    /// The withdrawal destination is still in its cooling-off period.
+    #[error("Destination cooling off")]
+    DestinationCoolingOff,
+
+    /// The owner provided does not own the vault.
+    #[error("Wrong vault owner")]
+    WrongOwner,
+
+    /// The account has not been initialized.
+    #[error("Account not initialized")]
+    NotInitialized,
+
+    /// The account has already been initialized.
+    #[error("Account already initialized")]
+    AlreadyInitialized,
+
+    /// The account is not owned by the vault program.
+    #[error("Account not owned by the vault program")]
+    InvalidAccountOwner,
+
+    /// A required signature, or enough multisig approvals, is missing.
+    #[error("Missing required signature")]
+    MissingSignature,
+
+    /// A time lock may only be extended, measured the same way.
+    #[error("Invalid time lock")]
+    InvalidLock,
+
+    /// A withdrawal limit must have a positive window.
+    #[error("Invalid withdrawal limit")]
+    InvalidLimit,
+
+    /// Invalid multisig account or configuration.
+    #[error("Invalid multisig")]
+    InvalidMultisig,
+
+    /// More intermediaries than the program config can hold.
+    #[error("Too many intermediaries")]
+    TooManyIntermediaries,
+
+    /// The owner approval does not match the instruction.
+    #[error("Invalid owner approval")]
+    InvalidApproval,
+
+    /// The owner approval nonce was already used.
+    #[error("Owner approval already used")]
+    StaleApproval,
+
+    /// No owner recovery is pending.
+    #[error("No pending recovery")]
+    NoPendingRecovery,
+
+    /// The owner recovery challenge period is not over yet.
+    #[error("Recovery challenge period not over")]
+    RecoveryNotReady,
+
+    /// The vault was not created at a program address, so it cannot sign.
+    #[error("Vault is not a program address")]
+    NotProgramAddress,
+
+    /// Invalid co-owner list or threshold.
+    #[error("Invalid co-owners")]
+    InvalidCoOwners,
+
+    /// Invalid beneficiary inactivity period.
+    #[error("Invalid inactivity period")]
+    InvalidInactivityPeriod,
+
+    /// The vault has no beneficiary.
+    #[error("No beneficiary")]
+    NoBeneficiary,
+
+    /// The owner has not been inactive for the inactivity period yet.
+    #[error("Owner not inactive")]
+    OwnerNotInactive,
+
+    /// The vault metadata exceeds the size cap.
+    #[error("Metadata too large")]
+    MetadataTooLarge,
+
+    /// The vault owner changed since the allowance or allowlist entry was granted.
+    #[error("Granted by a previous owner")]
+    OwnerChanged,
+
+    /// The recipient is not the one the allowance was granted for.
+    #[error("Wrong recipient")]
+    WrongRecipient,
+
+    /// An owner recovery is already pending.
+    #[error("Recovery already pending")]
+    RecoveryPending,
+
+    /// The vault still has open token or stake accounts.
+    #[error("Vault has open accounts")]
+    OpenAccounts,
 }
 impl From<VaultError> for ProgramError {
     fn from(e: VaultError) -> Self {
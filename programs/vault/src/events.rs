@@ -0,0 +1,448 @@
+use {
+    crate::state::{AuthorityMode, CoOwner, FeeSchedule, Inheritance, UnlockAt, WithdrawalLimit},
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{clock::UnixTimestamp, log::sol_log_data, pubkey::Pubkey},
+};
+
+/// Prefix of the transaction log lines carrying base64 encoded event data.
+pub const LOG_DATA_PREFIX: &str = "Program data: ";
+
+/// State changes logged by the vault program for indexers, Borsh-encoded as
+/// program data (see `sol_log_data`).
+///
+/// Every instruction that changes program state logs one. Variants are only
+/// ever appended so older indexers keep decoding the ones they know.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum VaultEvent {
+    /// A vault record was created or initialized.
+    Created {
+        /// The vault record address
+        vault: Pubkey,
+        /// The record owner
+        owner: Pubkey,
+        /// The securities intermediary (DART)
+        dart: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// Ownership of a vault changed.
+    OwnerChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The previous owner
+        previous_owner: Pubkey,
+        /// The new owner
+        new_owner: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault was closed, its balance paid out to the owner.
+    Closed {
+        /// The vault record address
+        vault: Pubkey,
+        /// The owner paid out to
+        owner: Pubkey,
+        /// Lamports paid out, fee included
+        amount: u64,
+        /// Lamports taken as the fee
+        fee: u64,
+        /// The new record version (the closed tombstone)
+        version: u8,
+    },
+
+    /// Lamports were withdrawn from a vault.
+    Withdrawn {
+        /// The vault record address
+        vault: Pubkey,
+        /// The account paid out to
+        recipient: Pubkey,
+        /// Lamports withdrawn, fee included
+        amount: u64,
+        /// Lamports taken as the fee
+        fee: u64,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault was frozen or thawed.
+    Frozen {
+        /// The vault record address
+        vault: Pubkey,
+        /// Whether the vault is now frozen
+        frozen: bool,
+        /// The new record version
+        version: u8,
+    },
+
+    /// Lamports were moved between two vaults.
+    Transferred {
+        /// The source vault record address
+        from: Pubkey,
+        /// The destination vault record address
+        to: Pubkey,
+        /// Lamports moved
+        amount: u64,
+        /// The new source record version
+        version: u8,
+    },
+
+    /// Tokens were withdrawn from a vault token account.
+    TokenWithdrawn {
+        /// The vault record address
+        vault: Pubkey,
+        /// The token mint
+        mint: Pubkey,
+        /// The token account paid out to
+        recipient: Pubkey,
+        /// Tokens withdrawn, in base units of the mint
+        amount: u64,
+    },
+
+    /// A co-owner's share of a vault changed hands.
+    ShareTransferred {
+        /// The vault record address
+        vault: Pubkey,
+        /// The previous co-owner
+        previous_owner: Pubkey,
+        /// The new co-owner
+        new_owner: Pubkey,
+        /// Weight of the share transferred
+        shares: u32,
+    },
+
+    /// A vault was moved to a new securities intermediary.
+    IntermediaryRotated {
+        /// The vault record address
+        vault: Pubkey,
+        /// The previous intermediary (DART)
+        previous_dart: Pubkey,
+        /// The new intermediary (DART)
+        new_dart: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The withdrawal limit of a vault was set or scheduled.
+    LimitChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new limit, none for no limit
+        limit: Option<WithdrawalLimit>,
+        /// When the limit takes effect
+        effective_at: UnixTimestamp,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A withdrawal destination was allowlisted.
+    DestinationAdded {
+        /// The vault record address
+        vault: Pubkey,
+        /// The allowlisted destination
+        destination: Pubkey,
+        /// When the cooling-off period ends
+        active_at: UnixTimestamp,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A withdrawal destination was removed from the allowlist.
+    DestinationRemoved {
+        /// The vault record address
+        vault: Pubkey,
+        /// The removed destination
+        destination: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The guardian of a vault was set or removed, cancelling any recovery.
+    GuardianChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new guardian, none for no guardian
+        guardian: Option<Pubkey>,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A guardian started recovering a vault. Completion logs `OwnerChanged`.
+    RecoveryStarted {
+        /// The vault record address
+        vault: Pubkey,
+        /// The owner the vault recovers to
+        new_owner: Pubkey,
+        /// When the recovery can complete
+        completes_at: UnixTimestamp,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A pending recovery was cancelled.
+    RecoveryCancelled {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The metadata of a vault was set or cleared.
+    MetadataChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// Whether the metadata account was closed
+        cleared: bool,
+        /// The new record version
+        version: u8,
+    },
+    /// A new owner was proposed for a vault. Acceptance logs `OwnerChanged`.
+    OwnerProposed {
+        /// The vault record address
+        vault: Pubkey,
+        /// The proposed owner
+        proposed_owner: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A pending owner proposal was cancelled.
+    OwnerProposalCancelled {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The custody arrangement of a vault changed.
+    AuthorityModeChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new authority mode
+        mode: AuthorityMode,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The time lock of a vault was set or extended.
+    LockExtended {
+        /// The vault record address
+        vault: Pubkey,
+        /// When the vault unlocks
+        unlock_at: UnlockAt,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A delegate was approved to withdraw from a vault.
+    AllowanceApproved {
+        /// The vault record address
+        vault: Pubkey,
+        /// The approved delegate
+        delegate: Pubkey,
+        /// The account the delegate may pay out to
+        recipient: Pubkey,
+        /// Lamports the delegate may withdraw
+        amount: u64,
+        /// When the allowance expires, none for never
+        expires_at: Option<UnixTimestamp>,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The allowance of a delegate was revoked.
+    AllowanceRevoked {
+        /// The vault record address
+        vault: Pubkey,
+        /// The delegate whose allowance was closed
+        delegate: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The co-owner list of a vault was set.
+    CoOwnersChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The co-owners and their shares
+        owners: Vec<CoOwner>,
+        /// Shares required to act for the vault
+        threshold: u8,
+        /// The new record version
+        version: u8,
+    },
+
+    /// The beneficiary of a vault was set or removed.
+    BeneficiaryChanged {
+        /// The vault record address
+        vault: Pubkey,
+        /// The new inheritance terms, none for no beneficiary
+        inheritance: Option<Inheritance>,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A token account owned by a vault was opened.
+    TokenAccountOpened {
+        /// The vault record address
+        vault: Pubkey,
+        /// The token mint
+        mint: Pubkey,
+        /// The associated token account
+        token_account: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A token account owned by a vault was closed.
+    TokenAccountClosed {
+        /// The vault record address
+        vault: Pubkey,
+        /// The closed token account
+        token_account: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// Lamports were moved from a vault into a new stake account.
+    StakeCreated {
+        /// The vault record address
+        vault: Pubkey,
+        /// The stake account
+        stake: Pubkey,
+        /// Lamports staked
+        amount: u64,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault stake account was delegated to a validator.
+    StakeDelegated {
+        /// The vault record address
+        vault: Pubkey,
+        /// The stake account
+        stake: Pubkey,
+        /// The vote account delegated to
+        vote: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault stake account was deactivated.
+    StakeDeactivated {
+        /// The vault record address
+        vault: Pubkey,
+        /// The stake account
+        stake: Pubkey,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault stake account was withdrawn back into the vault.
+    StakeWithdrawn {
+        /// The vault record address
+        vault: Pubkey,
+        /// The closed stake account
+        stake: Pubkey,
+        /// Lamports returned to the vault
+        amount: u64,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A vault record was migrated to the current layout.
+    Migrated {
+        /// The vault record address
+        vault: Pubkey,
+        /// The record version before migrating
+        previous_version: u8,
+        /// The new record version
+        version: u8,
+    },
+
+    /// A multisig account was initialized.
+    MultisigInitialized {
+        /// The multisig account address
+        multisig: Pubkey,
+        /// Signatures required
+        threshold: u8,
+        /// The signers
+        signers: Vec<Pubkey>,
+    },
+
+    /// The program config was initialized or updated.
+    ConfigUpdated {
+        /// The config account address
+        config: Pubkey,
+        /// The config admin
+        admin: Pubkey,
+        /// The fee schedule
+        fees: FeeSchedule,
+        /// The account receiving fees
+        fee_collector: Pubkey,
+        /// Whether the program is paused
+        paused: bool,
+        /// The recognized intermediaries (DARTs)
+        intermediaries: Vec<Pubkey>,
+    },
+}
+
+impl VaultEvent {
+    /// Log the event as program data.
+    pub fn emit(&self) {
+        if let Ok(data) = self.try_to_vec() {
+            sol_log_data(&[&data]);
+        }
+    }
+
+    /// Decode an event from program data logged by `emit`, if it is one.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        Self::try_from_slice(data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_withdrawn() {
+        let vault = Pubkey::new_from_array([7; 32]);
+        let recipient = Pubkey::new_from_array([99; 32]);
+        let event = VaultEvent::Withdrawn {
+            vault,
+            recipient,
+            amount: 1_000,
+            fee: 10,
+            version: 5,
+        };
+        let mut expected = vec![3];
+        expected.extend_from_slice(vault.as_ref());
+        expected.extend_from_slice(recipient.as_ref());
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.extend_from_slice(&10u64.to_le_bytes());
+        expected.push(5);
+        assert_eq!(event.try_to_vec().unwrap(), expected);
+        assert_eq!(VaultEvent::unpack(&expected), Some(event));
+        assert_eq!(VaultEvent::unpack(&expected[1..]), None);
+    }
+
+    #[test]
+    fn serialize_owner_proposed() {
+        let vault = Pubkey::new_from_array([7; 32]);
+        let proposed_owner = Pubkey::new_from_array([42; 32]);
+        let event = VaultEvent::OwnerProposed {
+            vault,
+            proposed_owner,
+            version: 9,
+        };
+        // Appended after the existing variants, so their tags are unchanged.
+        let mut expected = vec![16];
+        expected.extend_from_slice(vault.as_ref());
+        expected.extend_from_slice(proposed_owner.as_ref());
+        expected.push(9);
+        assert_eq!(event.try_to_vec().unwrap(), expected);
+        assert_eq!(VaultEvent::unpack(&expected), Some(event));
+    }
+}
@@ -1,87 +1,1629 @@
-use crate::id;
+use crate::{
+    id,
+    state::{
+        find_allowance_address, find_co_owners_address, find_config_address,
+        find_destination_address, find_metadata_address, find_stake_address, find_vault_address,
+        AuthorityMode, CoOwner, FeeSchedule, Inheritance, UnlockAt, VaultMetadata, WithdrawalLimit,
+    },
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    clock::UnixTimestamp,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    stake, system_program, sysvar,
 };
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+/// Instructions supported by the vault program.
+///
+/// Wherever a signature is required, a `Multisig` account may stand in for
+/// the signer, with enough of its signers appended as trailing `[signer]`
+/// accounts (see `with_multisig_signers`). The `CoOwners` list of a co-owned
+/// vault stands in for the owner signature the same way.
+///
+/// The owner signature of `CloseAccount`, `Withdraw` and `WithdrawTo` may be
+/// replaced by an ed25519 `OwnerApproval` (see `with_owner_approval`).
+///
+/// Every instruction the owner signs refreshes the owner's last activity,
+/// restarting the inactivity period of the vault's beneficiary.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+pub enum VaultInstruction {
+    /// Initialize a custodial vault record (by DART on behalf of a given owner)
+    /// in an account allocated by the caller.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be uninitialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    /// 3. `[]` The record owner (trader)
+    Initialize {
+        /// Time lock before which funds cannot leave the vault, if any
+        unlock_at: Option<UnlockAt>,
+    },
+
+    /// Transfer ownership of a vault record. A co-owned vault changes hands
+    /// one share at a time instead (see `TransferShare`).
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[]` The new record owner
+    TransferOwner,
+
+    /// Close a vault record account, paying the lamports above its rent-exempt
+    /// minimum to the current owner less the configured fee. The record data
+    /// is wiped and left marked as closed, so the vault can never be used or
+    /// re-initialized again. Fails while token or stake accounts opened
+    /// through `OpenTokenAccount` or `CreateStake` remain open.
+    ///
+    /// The account is deliberately left as a rent-exempt, program-owned
+    /// tombstone rather than drained and assigned back to the system program:
+    /// the runtime would delete a drained account, after which its address
+    /// could be created again and inherit the token and stake accounts the
+    /// closed vault was authority for.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?, writable]` The record owner (receiver of account lamports).
+    /// 4. `[writable]` The fee collector configured in the program config.
+    /// 5. `[writable]` The metadata account, at `find_metadata_address`. If the
+    ///    vault has metadata, it is closed too and its rent paid out with the
+    ///    vault balance.
+    ///
+    /// For a co-owned vault, the owner is the co-owner list, which is closed
+    /// too, and the lamports are split between the co-owners pro rata to
+    /// their shares (see `with_co_owner_wallets`).
+    CloseAccount,
+
+    /// Withdraw part of a vault balance to the current owner, keeping the
+    /// vault rent-exempt. The configured fee is taken from the amount.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?, writable]` The record owner (receiver of withdrawn lamports).
+    /// 4. `[writable]` The fee collector configured in the program config.
+    ///
+    /// For a co-owned vault, the lamports are split between the co-owners as
+    /// on `CloseAccount`.
+    Withdraw {
+        /// The number of lamports to withdraw
+        amount: u64,
+    },
+
+    /// Create a vault at a program derived address and initialize its record
+    /// (by DART on behalf of a given owner).
+    ///
+    /// The vault address is derived from `[b"vault", hash(dart, seed)]`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault address (must not exist yet).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    /// 3. `[]` The record owner (trader)
+    /// 4. `[signer, writable]` The payer of the vault rent.
+    /// 5. `[]` The system program
+    CreateVault {
+        /// The caller supplied address seed (max 32 bytes)
+        seed: String,
+        /// The canonical vault address bump seed (see `find_vault_address`)
+        bump: u8,
+        /// Signatures required by state-changing instructions
+        mode: AuthorityMode,
+        /// Time lock before which funds cannot leave the vault, if any
+        unlock_at: Option<UnlockAt>,
+    },
+
+    /// Initialize the singleton program config, with no fees and the admin
+    /// as fee collector.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The program config address (must not exist yet).
+    /// 1. `[signer]` The config admin authority.
+    /// 2. `[signer, writable]` The payer of the config rent.
+    /// 3. `[]` The system program
+    InitializeConfig {
+        /// The securities intermediaries (DARTs) recognized by the program
+        intermediaries: Vec<Pubkey>,
+    },
+
+    /// Update the program config.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The program config (must be previously initialized).
+    /// 1. `[signer]` The current config admin authority.
+    UpdateConfig {
+        /// The new admin authority, if changing
+        admin: Option<Pubkey>,
+        /// The new set of recognized intermediaries, if changing
+        intermediaries: Option<Vec<Pubkey>>,
+        /// The new fee schedule, if changing
+        fees: Option<FeeSchedule>,
+        /// The new fee collector, if changing
+        fee_collector: Option<Pubkey>,
+        /// Pause or resume ownership changes and withdrawals on every vault, if changing
+        paused: Option<bool>,
+    },
+
+    /// Propose a new owner for a vault record, pending acceptance by the new owner.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[]` The proposed new record owner
+    ProposeOwner,
+
+    /// Accept a pending ownership proposal, making the signer the record owner.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must have a pending owner).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The proposed new record owner
+    AcceptOwner,
+
+    /// Cancel a pending ownership proposal.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must have a pending owner).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    CancelOwnerProposal,
+
+    /// Change the signatures required by state-changing vault instructions.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    /// 3. `[signer]` The current record owner.
+    SetAuthorityMode {
+        /// The new authority mode
+        mode: AuthorityMode,
+    },
+
+    /// Migrate a vault record written by an older program version to the
+    /// current layout, reallocating the account and topping up its rent from
+    /// the payer. Migration keeps the owner and DART, and fills new fields with
+    /// their defaults.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account.
+    /// 1. `[signer, writable]` The payer of any additional rent.
+    /// 2. `[]` The system program.
+    Migrate,
+
+    /// Freeze a vault for a compliance hold, blocking ownership changes and
+    /// withdrawals until it is thawed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    Freeze,
+
+    /// Lift a compliance hold from a frozen vault.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    Thaw,
+
+    /// Set or extend the time lock of a vault. An existing lock can only be
+    /// pushed later, measured the same way (timestamp or slot).
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    ExtendLock {
+        /// The new end of the time lock
+        unlock_at: UnlockAt,
+    },
+
+    /// Initialize an M-of-N multisig authority in an account allocated by the
+    /// caller. The multisig can then be recognized as an intermediary (DART).
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The multisig account (must be uninitialized).
+    InitializeMultisig {
+        /// Number of signers required
+        threshold: u8,
+        /// The signers able to approve on behalf of the multisig
+        signers: Vec<Pubkey>,
+    },
+
+    /// Move a vault to a new securities intermediary (by the current DART or
+    /// the config admin). The vault address is unchanged.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The current securities intermediary (DART), or the config admin.
+    /// 3. `[]` The new securities intermediary, recognized by the program config.
+    RotateIntermediary,
+
+    /// Approve a delegate to withdraw up to an amount from a vault to a given
+    /// recipient, creating or replacing its allowance. The allowance lapses
+    /// once ownership of the vault changes.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The allowance account, derived from the vault and delegate.
+    /// 5. `[signer, writable]` The payer of the allowance rent.
+    /// 6. `[]` The system program.
+    Approve {
+        /// The delegate allowed to withdraw
+        delegate: Pubkey,
+        /// Lamports the delegate may withdraw
+        amount: u64,
+        /// Time after which the allowance can no longer be used, if any
+        expires_at: Option<UnixTimestamp>,
+        /// The only account the delegate may withdraw to
+        recipient: Pubkey,
+    },
+
+    /// Revoke a delegate's allowance, closing the allowance account.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The allowance account.
+    /// 5. `[writable]` The receiver of the allowance rent.
+    Revoke,
+
+    /// Withdraw from a vault (by a delegate), drawing down its allowance.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The delegate.
+    /// 3. `[writable]` The allowance account.
+    /// 4. `[writable]` The recipient named in the allowance.
+    /// 5. `[writable]` The fee collector named in the program config.
+    DelegatedWithdraw {
+        /// Lamports to withdraw, fee included
+        amount: u64,
+    },
+
+    /// Set the withdrawal limit of a vault. A stricter limit applies at once
+    /// and may be set by the DART or owner; a raised limit, or lifting the
+    /// limit, only takes effect after `WithdrawalLimit::RAISE_DELAY` and may
+    /// only be set by the DART.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART), or the record owner.
+    SetWithdrawalLimit {
+        /// The new limit, or none to lift the limit
+        limit: Option<WithdrawalLimit>,
+    },
+
+    /// Allowlist a withdrawal destination for a vault (by owner plus DART).
+    /// The destination can be withdrawn to once `Destination::COOLING_OFF`
+    /// has passed, until the vault changes hands. A new owner may allowlist
+    /// the destination again, restarting the cooling-off period.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    /// 3. `[signer]` The current record owner.
+    /// 4. `[writable]` The allowlist entry, derived from the vault and destination.
+    /// 5. `[signer, writable]` The payer of the allowlist entry rent.
+    /// 6. `[]` The system program.
+    AddDestination {
+        /// The address funds may be withdrawn to
+        destination: Pubkey,
+    },
+
+    /// Remove a withdrawal destination from a vault allowlist (by owner plus
+    /// DART), closing the allowlist entry.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The securities intermediary (DART)
+    /// 3. `[signer]` The current record owner.
+    /// 4. `[writable]` The allowlist entry.
+    /// 5. `[writable]` The receiver of the allowlist entry rent.
+    RemoveDestination,
+
+    /// Withdraw part of a vault balance to an allowlisted destination.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[]` The allowlist entry of the destination.
+    /// 5. `[writable]` The destination.
+    /// 6. `[writable]` The fee collector named in the program config.
+    WithdrawTo {
+        /// The allowlisted address to withdraw to
+        destination: Pubkey,
+        /// Lamports to withdraw, fee included
+        amount: u64,
+    },
+
+    /// Move lamports from one vault to another vault of the same securities
+    /// intermediary (DART), keeping the source rent-exempt. No fee is taken.
+    ///
+    /// Signers are required per the source vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The source vault record account (must be previously initialized).
+    /// 1. `[writable]` The destination vault record account (must be previously initialized).
+    /// 2. `[]` The program config
+    /// 3. `[signer?]` The securities intermediary (DART)
+    /// 4. `[signer?]` The source record owner.
+    InternalTransfer {
+        /// Lamports to move
+        amount: u64,
+    },
+
+    /// Set the guardian able to recover the vault to a new owner, or remove
+    /// it. Any pending recovery is cancelled.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    SetGuardian {
+        /// The guardian key, or a `Multisig` for a threshold of guardians
+        guardian: Option<Pubkey>,
+    },
+
+    /// Start recovering the vault to a new owner (by the guardian). The
+    /// recovery completes after `Recovery::CHALLENGE_PERIOD`, unless the
+    /// current owner cancels it. A pending recovery must be cancelled before
+    /// another one can start.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The guardian on record.
+    StartRecovery {
+        /// The owner to recover the vault to
+        new_owner: Pubkey,
+    },
+
+    /// Cancel a pending owner recovery.
+    ///
+    /// The owner's signature alone is enough, whatever the vault's
+    /// `AuthorityMode`; otherwise signers are required per that mode.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    CancelRecovery,
+
+    /// Complete an owner recovery once its challenge period is over (by anyone).
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    CompleteRecovery,
+
+    /// Open the associated token account of a vault for a mint (by anyone),
+    /// under either SPL Token or Token-2022. The vault must have been created
+    /// at a program address (see `CreateVault`) to sign for its tokens.
+    ///
+    /// The vault cannot be closed until the token account is closed again, as a
+    /// closed vault can no longer sign for it.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable, signer]` The fee payer, funding the token account.
+    /// 1. `[writable]` The vault record account (must be previously initialized).
+    /// 2. `[writable]` The vault associated token account.
+    /// 3. `[]` The token mint.
+    /// 4. `[]` The system program
+    /// 5. `[]` The token program
+    /// 6. `[]` The associated token account program
+    OpenTokenAccount,
+
+    /// Withdraw tokens from a vault associated token account to the owner's
+    /// associated token account. Mints requiring extra transfer accounts, such
+    /// as Token-2022 transfer hooks, are not supported.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The vault associated token account.
+    /// 5. `[]` The token mint.
+    /// 6. `[writable]` The owner associated token account.
+    /// 7. `[]` The token program
+    WithdrawToken {
+        /// Tokens to withdraw, in base units of the mint
+        amount: u64,
+    },
+
+    /// Close an empty vault associated token account, returning its rent to
+    /// the vault.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The vault associated token account.
+    /// 5. `[]` The token program
+    CloseTokenAccount,
+
+    /// Create a stake account funded from the vault, with the vault as stake
+    /// and withdraw authority. The fee payer funds the rent-exempt reserve of
+    /// the stake account. The vault must have been created at a program
+    /// address (see `CreateVault`), and cannot be closed until the stake is
+    /// withdrawn (see `WithdrawStake`).
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable, signer]` The fee payer.
+    /// 1. `[writable]` The vault record account (must be previously initialized).
+    /// 2. `[]` The program config
+    /// 3. `[signer?]` The securities intermediary (DART)
+    /// 4. `[signer?]` The current record owner.
+    /// 5. `[writable]` The stake account, at `find_stake_address`.
+    /// 6. `[]` The rent sysvar
+    /// 7. `[]` The system program
+    /// 8. `[]` The stake program
+    CreateStake {
+        /// Index of the stake account among those of the vault
+        index: u64,
+        /// Lamports to stake from the vault
+        amount: u64,
+    },
+
+    /// Delegate a vault stake account to a validator.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The stake account.
+    /// 5. `[]` The validator vote account.
+    /// 6. `[]` The clock sysvar
+    /// 7. `[]` The stake history sysvar
+    /// 8. `[]` The stake config account
+    /// 9. `[]` The stake program
+    DelegateStake,
+
+    /// Deactivate a vault stake account.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The stake account.
+    /// 5. `[]` The clock sysvar
+    /// 6. `[]` The stake program
+    DeactivateStake,
+
+    /// Withdraw the whole balance of an inactive vault stake account back into
+    /// the vault, closing the stake account.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The stake account.
+    /// 5. `[]` The clock sysvar
+    /// 6. `[]` The stake history sysvar
+    /// 7. `[]` The stake program
+    WithdrawStake,
+
+    /// Make a vault co-owned, or replace its co-owners. The vault owner
+    /// becomes the co-owner list account.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable, signer]` The fee payer.
+    /// 1. `[writable]` The vault record account (must be previously initialized).
+    /// 2. `[]` The program config
+    /// 3. `[signer?]` The securities intermediary (DART)
+    /// 4. `[signer?]` The current record owner.
+    /// 5. `[writable]` The co-owner list, at `find_co_owners_address`.
+    /// 6. `[]` The system program
+    SetCoOwners {
+        /// The co-owners and their shares
+        owners: Vec<CoOwner>,
+        /// Number of co-owners required to approve in place of the owner
+        threshold: u8,
+    },
+
+    /// Set the beneficiary able to claim the vault after a period of owner
+    /// inactivity, or remove it. The inactivity period restarts.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    SetBeneficiary {
+        /// The beneficiary and inactivity period
+        inheritance: Option<Inheritance>,
+    },
+
+    /// Take ownership of the vault (by the beneficiary) once the owner has
+    /// been inactive for the inactivity period.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer]` The beneficiary on record.
+    ClaimInactive,
+
+    /// Attach metadata to the vault, replacing any previous metadata. The
+    /// metadata account is created on first use, the payer covering its rent.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable, signer]` The fee payer.
+    /// 1. `[writable]` The vault record account (must be previously initialized).
+    /// 2. `[]` The program config
+    /// 3. `[signer?]` The securities intermediary (DART)
+    /// 4. `[signer?]` The current record owner.
+    /// 5. `[writable]` The metadata account, at `find_metadata_address`.
+    /// 6. `[]` The system program
+    SetMetadata {
+        /// The metadata, at most `VaultMetadata::MAX_LEN` bytes packed
+        metadata: VaultMetadata,
+    },
+
+    /// Remove the vault metadata, closing the metadata account.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The current record owner.
+    /// 4. `[writable]` The metadata account, at `find_metadata_address`.
+    /// 5. `[writable]` The account receiving the metadata account rent
+    ClearMetadata,
+
+    /// Transfer a single co-owner's share of a co-owned vault. The co-owner
+    /// signs in place of the co-owner list.
+    ///
+    /// Signers are required per the vault's `AuthorityMode`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The vault record account (must be previously initialized).
+    /// 1. `[]` The program config
+    /// 2. `[signer?]` The securities intermediary (DART)
+    /// 3. `[signer?]` The co-owner whose share is transferred.
+    /// 4. `[]` The new co-owner
+    /// 5. `[writable]` The co-owner list, at `find_co_owners_address`.
+    TransferShare,
+}
+
+/// Create a `VaultInstruction::Initialize` instruction
+pub fn initialize(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    unlock_at: Option<UnlockAt>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Initialize { unlock_at },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+            AccountMeta::new_readonly(*owner, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::TransferOwner` instruction
+pub fn transfer_owner(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    new_owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::TransferOwner,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new_readonly(*new_owner, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CloseAccount` instruction
+pub fn close_account(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    fee_collector: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CloseAccount,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new(*owner, mode.owner_signs()),
+            AccountMeta::new(*fee_collector, false),
+            AccountMeta::new(find_metadata_address(pda).0, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Withdraw` instruction
+pub fn withdraw(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    fee_collector: &Pubkey,
+    amount: u64,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Withdraw { amount },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new(*owner, mode.owner_signs()),
+            AccountMeta::new(*fee_collector, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CreateVault` instruction
+pub fn create_vault(
+    payer: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    seed: &str,
+    mode: AuthorityMode,
+    unlock_at: Option<UnlockAt>,
+) -> Instruction {
+    let (pda, bump) = find_vault_address(dart, seed);
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CreateVault {
+            seed: seed.to_string(),
+            bump,
+            mode,
+            unlock_at,
+        },
+        vec![
+            AccountMeta::new(pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::InitializeConfig` instruction
+pub fn initialize_config(
+    admin: &Pubkey,
+    payer: &Pubkey,
+    intermediaries: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::InitializeConfig { intermediaries },
+        vec![
+            AccountMeta::new(find_config_address().0, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::UpdateConfig` instruction
+pub fn update_config(
+    admin: &Pubkey,
+    new_admin: Option<Pubkey>,
+    intermediaries: Option<Vec<Pubkey>>,
+    fees: Option<FeeSchedule>,
+    fee_collector: Option<Pubkey>,
+    paused: Option<bool>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::UpdateConfig {
+            admin: new_admin,
+            intermediaries,
+            fees,
+            fee_collector,
+            paused,
+        },
+        vec![
+            AccountMeta::new(find_config_address().0, false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::ProposeOwner` instruction
+pub fn propose_owner(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    new_owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::ProposeOwner,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new_readonly(*new_owner, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::AcceptOwner` instruction
+pub fn accept_owner(pda: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::AcceptOwner,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*new_owner, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CancelOwnerProposal` instruction
+pub fn cancel_owner_proposal(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CancelOwnerProposal,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::SetAuthorityMode` instruction
+pub fn set_authority_mode(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::SetAuthorityMode { mode },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Migrate` instruction
+pub fn migrate(pda: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Migrate,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Freeze` instruction
+pub fn freeze(pda: &Pubkey, dart: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Freeze,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Thaw` instruction
+pub fn thaw(pda: &Pubkey, dart: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Thaw,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::ExtendLock` instruction
+pub fn extend_lock(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    unlock_at: UnlockAt,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::ExtendLock { unlock_at },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::InitializeMultisig` instruction
+pub fn initialize_multisig(multisig: &Pubkey, threshold: u8, signers: Vec<Pubkey>) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::InitializeMultisig { threshold, signers },
+        vec![AccountMeta::new(*multisig, false)],
+    )
+}
+
+/// Create a `VaultInstruction::RotateIntermediary` instruction
+pub fn rotate_intermediary(pda: &Pubkey, authority: &Pubkey, new_dart: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::RotateIntermediary,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*new_dart, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Approve` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn approve(
+    payer: &Pubkey,
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    expires_at: Option<UnixTimestamp>,
+    recipient: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Approve {
+            delegate: *delegate,
+            amount,
+            expires_at,
+            recipient: *recipient,
+        },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_allowance_address(pda, delegate).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::Revoke` instruction
+pub fn revoke(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    receiver: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::Revoke,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_allowance_address(pda, delegate).0, false),
+            AccountMeta::new(*receiver, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::DelegatedWithdraw` instruction
+pub fn delegated_withdraw(
+    pda: &Pubkey,
+    delegate: &Pubkey,
+    recipient: &Pubkey,
+    fee_collector: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::DelegatedWithdraw { amount },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*delegate, true),
+            AccountMeta::new(find_allowance_address(pda, delegate).0, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*fee_collector, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::SetWithdrawalLimit` instruction
+pub fn set_withdrawal_limit(
+    pda: &Pubkey,
+    authority: &Pubkey,
+    limit: Option<WithdrawalLimit>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::SetWithdrawalLimit { limit },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::AddDestination` instruction
+pub fn add_destination(
+    payer: &Pubkey,
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::AddDestination {
+            destination: *destination,
+        },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(find_destination_address(pda, destination).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::RemoveDestination` instruction
+pub fn remove_destination(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::RemoveDestination,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, true),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(find_destination_address(pda, destination).0, false),
+            AccountMeta::new(*receiver, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::WithdrawTo` instruction
+pub fn withdraw_to(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+    fee_collector: &Pubkey,
+    amount: u64,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::WithdrawTo {
+            destination: *destination,
+            amount,
+        },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new_readonly(find_destination_address(pda, destination).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(*fee_collector, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::InternalTransfer` instruction
+pub fn internal_transfer(
+    from: &Pubkey,
+    to: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::InternalTransfer { amount },
+        vec![
+            AccountMeta::new(*from, false),
+            AccountMeta::new(*to, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::SetGuardian` instruction
+pub fn set_guardian(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    guardian: Option<Pubkey>,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::SetGuardian { guardian },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::StartRecovery` instruction
+pub fn start_recovery(pda: &Pubkey, guardian: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::StartRecovery {
+            new_owner: *new_owner,
+        },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*guardian, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CancelRecovery` instruction
+pub fn cancel_recovery(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CancelRecovery,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CompleteRecovery` instruction
+pub fn complete_recovery(pda: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CompleteRecovery,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::OpenTokenAccount` instruction
+pub fn open_token_account(
+    payer: &Pubkey,
+    pda: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::OpenTokenAccount,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*pda, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(pda, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::WithdrawToken` instruction
+pub fn withdraw_token(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::WithdrawToken { amount },
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(pda, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(owner, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CloseTokenAccount` instruction
+pub fn close_token_account(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CloseTokenAccount,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(pda, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::CreateStake` instruction
+pub fn create_stake(
+    payer: &Pubkey,
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    index: u64,
+    amount: u64,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::CreateStake { index, amount },
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_stake_address(pda, index).0, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(stake::program::id(), false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::DelegateStake` instruction
+pub fn delegate_stake(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    stake_account: &Pubkey,
+    vote_account: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::DelegateStake,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(*stake_account, false),
+            AccountMeta::new_readonly(*vote_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+            #[allow(deprecated)]
+            AccountMeta::new_readonly(stake::config::id(), false),
+            AccountMeta::new_readonly(stake::program::id(), false),
+        ],
+    )
+}
 
-/// Instructions supported by the vault program.
-#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
-pub enum VaultInstruction {
-    /// Initialize a vault record (by DART on behalf of a given owner).
-    ///
-    /// Accounts expected by this instruction:
-    ///
-    /// 0. `[writable]` The vault record account (must be uninitialized).
-    /// 1. `[signer]` The securities intermediary (DART)
-    /// 2. `[]` The record owner (trader)
-    Initialize,
+/// Create a `VaultInstruction::DeactivateStake` instruction
+pub fn deactivate_stake(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    stake_account: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::DeactivateStake,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(*stake_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(stake::program::id(), false),
+        ],
+    )
+}
 
-    /// Transfer ownership of a vault record
-    ///
-    /// Accounts expected by this instruction:
-    ///
-    /// 0. `[writable]` The vault record account (must be previously initialized).
-    /// 1. `[signer]` The securities intermediary (DART)
-    /// 2. `[signer]` The current record owner.
-    /// 3. `[]` The new record owner
-    TransferOwner,
+/// Create a `VaultInstruction::WithdrawStake` instruction
+pub fn withdraw_stake(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    stake_account: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::WithdrawStake,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(*stake_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::stake_history::id(), false),
+            AccountMeta::new_readonly(stake::program::id(), false),
+        ],
+    )
+}
 
-    /// Close a vault record account, draining lamports to the current owner.
-    ///
-    /// Accounts expected by this instruction:
-    ///
-    /// 0. `[writable]` The vault record account (must be previously initialized).
-    /// 1. `[signer]` The securities intermediary (DART)
-    /// 2. `[signer, writable]` The record owner (receiver of account lamports).
-    CloseAccount,
+/// Create a `VaultInstruction::SetCoOwners` instruction
+pub fn set_co_owners(
+    payer: &Pubkey,
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    owners: Vec<CoOwner>,
+    threshold: u8,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::SetCoOwners { owners, threshold },
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_co_owners_address(pda).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
 }
 
-/// Create a `VaultInstruction::Initialize` instruction
-pub fn initialize(pda: &Pubkey, dart: &Pubkey, owner: &Pubkey) -> Instruction {
+/// Create a `VaultInstruction::TransferShare` instruction
+pub fn transfer_share(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    co_owner: &Pubkey,
+    new_co_owner: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::TransferShare,
+        vec![
+            AccountMeta::new_readonly(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*co_owner, mode.owner_signs()),
+            AccountMeta::new_readonly(*new_co_owner, false),
+            AccountMeta::new(find_co_owners_address(pda).0, false),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::SetBeneficiary` instruction
+pub fn set_beneficiary(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    inheritance: Option<Inheritance>,
+    mode: AuthorityMode,
+) -> Instruction {
     Instruction::new_with_borsh(
         id(),
-        &VaultInstruction::Initialize,
+        &VaultInstruction::SetBeneficiary { inheritance },
         vec![
             AccountMeta::new(*pda, false),
-            AccountMeta::new_readonly(*dart, true),
-            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
         ],
     )
 }
 
-/// Create a `VaultInstruction::TransferOwner` instruction
-pub fn transfer_owner(
+/// Create a `VaultInstruction::ClaimInactive` instruction
+pub fn claim_inactive(pda: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        id(),
+        &VaultInstruction::ClaimInactive,
+        vec![
+            AccountMeta::new(*pda, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*beneficiary, true),
+        ],
+    )
+}
+
+/// Create a `VaultInstruction::SetMetadata` instruction
+pub fn set_metadata(
+    payer: &Pubkey,
     pda: &Pubkey,
     dart: &Pubkey,
     owner: &Pubkey,
-    new_owner: &Pubkey,
+    metadata: VaultMetadata,
+    mode: AuthorityMode,
 ) -> Instruction {
     Instruction::new_with_borsh(
         id(),
-        &VaultInstruction::TransferOwner,
+        &VaultInstruction::SetMetadata { metadata },
         vec![
+            AccountMeta::new(*payer, true),
             AccountMeta::new(*pda, false),
-            AccountMeta::new_readonly(*dart, true),
-            AccountMeta::new_readonly(*owner, false),
-            AccountMeta::new_readonly(*new_owner, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_metadata_address(pda).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
         ],
     )
 }
 
-/// Create a `VaultInstruction::CloseAccount` instruction
-pub fn close_account(pda: &Pubkey, dart: &Pubkey, owner: &Pubkey) -> Instruction {
+/// Create a `VaultInstruction::ClearMetadata` instruction
+pub fn clear_metadata(
+    pda: &Pubkey,
+    dart: &Pubkey,
+    owner: &Pubkey,
+    receiver: &Pubkey,
+    mode: AuthorityMode,
+) -> Instruction {
     Instruction::new_with_borsh(
         id(),
-        &VaultInstruction::CloseAccount,
+        &VaultInstruction::ClearMetadata,
         vec![
             AccountMeta::new(*pda, false),
-            AccountMeta::new_readonly(*dart, true),
-            AccountMeta::new(*owner, false),
+            AccountMeta::new_readonly(find_config_address().0, false),
+            AccountMeta::new_readonly(*dart, mode.dart_signs()),
+            AccountMeta::new_readonly(*owner, mode.owner_signs()),
+            AccountMeta::new(find_metadata_address(pda).0, false),
+            AccountMeta::new(*receiver, false),
         ],
     )
 }
 
+/// Adapt a `CloseAccount` or `Withdraw` instruction for a co-owned vault: the
+/// wallets of the co-owners, receiving their shares, are appended.
+pub fn with_co_owner_wallets(mut instruction: Instruction, wallets: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(
+        wallets
+            .iter()
+            .map(|wallet| AccountMeta::new(*wallet, false)),
+    );
+    instruction
+}
+
+/// Adapt an instruction for a multisig authority: the multisig account no
+/// longer signs itself, and the given multisig signers are appended instead.
+pub fn with_multisig_signers(
+    mut instruction: Instruction,
+    multisig: &Pubkey,
+    signers: &[Pubkey],
+) -> Instruction {
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == *multisig {
+            account.is_signer = false;
+        }
+    }
+    instruction.accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true)),
+    );
+    instruction
+}
+
+/// Adapt an instruction for an off-chain owner approval: the owner no longer
+/// signs, and the instructions sysvar is appended so the approval can be read
+/// from the preceding ed25519 instruction (see `OwnerApproval`).
+pub fn with_owner_approval(mut instruction: Instruction, owner: &Pubkey) -> Instruction {
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == *owner {
+            account.is_signer = false;
+        }
+    }
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    instruction
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,8 +1631,11 @@ mod tests {
 
     #[test]
     fn serialize_initialize() {
-        let instruction = VaultInstruction::Initialize;
-        let expected = vec![0];
+        let instruction = VaultInstruction::Initialize {
+            unlock_at: Some(UnlockAt::Slot(1_000)),
+        };
+        let mut expected = vec![0, 1, 1];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
         assert_eq!(instruction.try_to_vec().unwrap(), expected);
         assert_eq!(
             VaultInstruction::try_from_slice(&expected).unwrap(),
@@ -120,9 +1665,377 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serialize_withdraw() {
+        let instruction = VaultInstruction::Withdraw { amount: 1_000 };
+        let mut expected = vec![3];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_create_vault() {
+        let instruction = VaultInstruction::CreateVault {
+            seed: "abc".to_string(),
+            bump: 255,
+            mode: AuthorityMode::SelfCustody,
+            unlock_at: None,
+        };
+        let mut expected = vec![4];
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        expected.extend_from_slice(b"abc");
+        expected.push(255);
+        expected.push(1);
+        expected.push(0);
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_initialize_config() {
+        let dart = Pubkey::new_from_array([66; 32]);
+        let instruction = VaultInstruction::InitializeConfig {
+            intermediaries: vec![dart],
+        };
+        let mut expected = vec![5];
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(dart.as_ref());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_update_config() {
+        let admin = Pubkey::new_from_array([99; 32]);
+        let instruction = VaultInstruction::UpdateConfig {
+            admin: Some(admin),
+            intermediaries: None,
+            fees: Some(FeeSchedule {
+                basis_points: 25,
+                minimum: 5_000,
+            }),
+            fee_collector: None,
+            paused: Some(true),
+        };
+        let mut expected = vec![6, 1];
+        expected.extend_from_slice(admin.as_ref());
+        expected.extend_from_slice(&[0, 1]);
+        expected.extend_from_slice(&25u16.to_le_bytes());
+        expected.extend_from_slice(&5_000u64.to_le_bytes());
+        expected.extend_from_slice(&[0, 1, 1]);
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_owner_proposal() {
+        let instructions = [
+            (VaultInstruction::ProposeOwner, 7),
+            (VaultInstruction::AcceptOwner, 8),
+            (VaultInstruction::CancelOwnerProposal, 9),
+            (VaultInstruction::Migrate, 11),
+            (VaultInstruction::Freeze, 12),
+            (VaultInstruction::Thaw, 13),
+            (VaultInstruction::RotateIntermediary, 16),
+            (VaultInstruction::Revoke, 18),
+            (VaultInstruction::RemoveDestination, 22),
+            (VaultInstruction::CancelRecovery, 27),
+            (VaultInstruction::CompleteRecovery, 28),
+            (VaultInstruction::OpenTokenAccount, 29),
+            (VaultInstruction::CloseTokenAccount, 31),
+            (VaultInstruction::DelegateStake, 33),
+            (VaultInstruction::DeactivateStake, 34),
+            (VaultInstruction::ClaimInactive, 38),
+            (VaultInstruction::ClearMetadata, 40),
+            (VaultInstruction::TransferShare, 41),
+            (VaultInstruction::WithdrawStake, 35),
+        ];
+        for (instruction, tag) in instructions {
+            let expected = vec![tag];
+            assert_eq!(instruction.try_to_vec().unwrap(), expected);
+            assert_eq!(
+                VaultInstruction::try_from_slice(&expected).unwrap(),
+                instruction
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_set_authority_mode() {
+        let instruction = VaultInstruction::SetAuthorityMode {
+            mode: AuthorityMode::CoSign,
+        };
+        let expected = vec![10, 2];
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_extend_lock() {
+        let instruction = VaultInstruction::ExtendLock {
+            unlock_at: UnlockAt::Timestamp(1_700_000_000),
+        };
+        let mut expected = vec![14, 0];
+        expected.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_initialize_multisig() {
+        let signer = Pubkey::new_from_array([66; 32]);
+        let instruction = VaultInstruction::InitializeMultisig {
+            threshold: 1,
+            signers: vec![signer],
+        };
+        let mut expected = vec![15, 1];
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(signer.as_ref());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_approve() {
+        let delegate = Pubkey::new_from_array([66; 32]);
+        let recipient = Pubkey::new_from_array([77; 32]);
+        let instruction = VaultInstruction::Approve {
+            delegate,
+            amount: 1_000,
+            expires_at: Some(1_700_000_000),
+            recipient,
+        };
+        let mut expected = vec![17];
+        expected.extend_from_slice(delegate.as_ref());
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.push(1);
+        expected.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        expected.extend_from_slice(recipient.as_ref());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_delegated_withdraw() {
+        let instruction = VaultInstruction::DelegatedWithdraw { amount: 1_000 };
+        let mut expected = vec![19];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_set_withdrawal_limit() {
+        let instruction = VaultInstruction::SetWithdrawalLimit {
+            limit: Some(WithdrawalLimit {
+                amount: 1_000,
+                window: 86_400,
+            }),
+        };
+        let mut expected = vec![20, 1];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        expected.extend_from_slice(&86_400i64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_withdraw_to() {
+        let destination = Pubkey::new_from_array([66; 32]);
+        let instruction = VaultInstruction::WithdrawTo {
+            destination,
+            amount: 1_000,
+        };
+        let mut expected = vec![23];
+        expected.extend_from_slice(destination.as_ref());
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_internal_transfer() {
+        let instruction = VaultInstruction::InternalTransfer { amount: 1_000 };
+        let mut expected = vec![24];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_start_recovery() {
+        let new_owner = Pubkey::new_from_array([99; 32]);
+        let instruction = VaultInstruction::StartRecovery { new_owner };
+        let mut expected = vec![26];
+        expected.extend_from_slice(new_owner.as_ref());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_withdraw_token() {
+        let instruction = VaultInstruction::WithdrawToken { amount: 1_000 };
+        let mut expected = vec![30];
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_create_stake() {
+        let instruction = VaultInstruction::CreateStake {
+            index: 2,
+            amount: 1_000,
+        };
+        let mut expected = vec![32];
+        expected.extend_from_slice(&2u64.to_le_bytes());
+        expected.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_set_co_owners() {
+        let owner = Pubkey::new_from_array([99; 32]);
+        let instruction = VaultInstruction::SetCoOwners {
+            owners: vec![CoOwner { owner, shares: 3 }],
+            threshold: 1,
+        };
+        let mut expected = vec![36];
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(owner.as_ref());
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        expected.push(1);
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_set_beneficiary() {
+        let beneficiary = Pubkey::new_from_array([99; 32]);
+        let instruction = VaultInstruction::SetBeneficiary {
+            inheritance: Some(Inheritance {
+                beneficiary,
+                inactivity_period: 3_600,
+            }),
+        };
+        let mut expected = vec![37, 1];
+        expected.extend_from_slice(beneficiary.as_ref());
+        expected.extend_from_slice(&3_600i64.to_le_bytes());
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn serialize_set_metadata() {
+        let instruction = VaultInstruction::SetMetadata {
+            metadata: VaultMetadata {
+                reference: "ref".to_string(),
+                label: String::new(),
+                category: "a".to_string(),
+            },
+        };
+        let mut expected = vec![39];
+        expected.extend_from_slice(&3u32.to_le_bytes());
+        expected.extend_from_slice(b"ref");
+        expected.extend_from_slice(&0u32.to_le_bytes());
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(b"a");
+        assert_eq!(instruction.try_to_vec().unwrap(), expected);
+        assert_eq!(
+            VaultInstruction::try_from_slice(&expected).unwrap(),
+            instruction
+        );
+    }
+
+    #[test]
+    fn multisig_signers_replace_authority_signature() {
+        let pda = Pubkey::new_unique();
+        let multisig = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let instruction = with_multisig_signers(
+            close_account(&pda, &multisig, &owner, &owner, AuthorityMode::Custodial),
+            &multisig,
+            &signers,
+        );
+        assert!(!instruction.accounts[2].is_signer);
+        assert_eq!(instruction.accounts.len(), 8);
+        assert!(instruction.accounts[6..].iter().all(|a| a.is_signer));
+    }
+
+    #[test]
+    fn signers_follow_authority_mode() {
+        let pda = Pubkey::new_unique();
+        let dart = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let modes = [
+            (AuthorityMode::Custodial, true, false),
+            (AuthorityMode::SelfCustody, false, true),
+            (AuthorityMode::CoSign, true, true),
+        ];
+        for (mode, dart_signs, owner_signs) in modes {
+            let instruction = transfer_owner(&pda, &dart, &owner, &Pubkey::new_unique(), mode);
+            assert_eq!(instruction.accounts[2].is_signer, dart_signs);
+            assert_eq!(instruction.accounts[3].is_signer, owner_signs);
+        }
+    }
+
     #[test]
     fn deserialize_invalid_instruction() {
-        let expected = vec![12]; // Invalid instruction numeric
+        let expected = vec![99]; // Invalid instruction numeric
         let err: ProgramError = VaultInstruction::try_from_slice(&expected)
             .unwrap_err()
             .into();
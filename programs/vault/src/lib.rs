@@ -1,6 +1,8 @@
 #![forbid(unsafe_code)]
+pub mod approval;
 mod entrypoint;
 pub mod error;
+pub mod events;
 pub mod instruction;
 pub mod processor;
 pub mod state;
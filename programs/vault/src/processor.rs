@@ -1,28 +1,679 @@
 use {
-    crate::{error::VaultError, instruction::VaultInstruction, state::VaultRecord},
+    crate::{
+        approval::{ApprovalAction, OwnerApproval},
+        error::VaultError,
+        events::VaultEvent,
+        instruction::VaultInstruction,
+        state::{
+            vault_seed, Allowance, AuthorityMode, CoOwner, CoOwners, Destination, FeeSchedule,
+            Inheritance, Metadata, Multisig, PendingLimit, ProgramConfig, Recovery, UnlockAt,
+            VaultMetadata, VaultRecord, WithdrawalLimit, ALLOWANCE_SEED_PREFIX, CONFIG_SEED,
+            CO_OWNERS_SEED_PREFIX, DESTINATION_SEED_PREFIX, METADATA_SEED_PREFIX,
+            STAKE_SEED_PREFIX, VAULT_SEED_PREFIX,
+        },
+    },
     borsh::BorshDeserialize,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
+        clock::{Clock, UnixTimestamp},
         entrypoint::ProgramResult,
         msg,
+        program::{invoke, invoke_signed},
         program_error::ProgramError,
         program_pack::IsInitialized,
-        pubkey::Pubkey,
+        pubkey::{Pubkey, MAX_SEED_LEN},
+        rent::Rent,
+        stake::{
+            self,
+            state::{Authorized, Lockup, StakeStateV2},
+        },
+        system_instruction, system_program,
+        sysvar::{self, Sysvar},
+    },
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id,
+        instruction::create_associated_token_account_idempotent,
     },
+    spl_token_2022::{extension::StateWithExtensions, state::Mint},
 };
 
-fn validate_signer(account: &AccountInfo, key: &Pubkey) -> ProgramResult {
+// Ensure an account signed, or for a multisig or co-owner list account, that
+// enough of its signers did among the given trailing accounts.
+fn validate_signer(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    key: &Pubkey,
+    signers: &[AccountInfo],
+) -> ProgramResult {
     if key != account.key {
         msg!("Account key mismatch");
         return Err(VaultError::IncorrectAccountKey.into());
     }
+    if account.owner == program_id && account.data_len() == Multisig::LEN {
+        let multisig = Multisig::unpack(&account.data.borrow())?;
+        if multisig.is_initialized() {
+            let approvals = multisig.approvals(signers);
+            if approvals < usize::from(multisig.threshold) {
+                msg!(
+                    "Multisig approvals below threshold: {} of {}",
+                    approvals,
+                    multisig.threshold
+                );
+                return Err(VaultError::MissingSignature.into());
+            }
+            return Ok(());
+        }
+    }
+    if account.owner == program_id && account.data_len() == CoOwners::LEN {
+        let co_owners = CoOwners::unpack(&account.data.borrow())?;
+        if co_owners.is_initialized() {
+            let approvals = co_owners.approvals(signers);
+            if approvals < usize::from(co_owners.threshold) {
+                msg!(
+                    "Co-owner approvals below threshold: {} of {}",
+                    approvals,
+                    co_owners.threshold
+                );
+                return Err(VaultError::MissingSignature.into());
+            }
+            return Ok(());
+        }
+    }
     if !account.is_signer {
         msg!("Missing required signature");
-        return Err(ProgramError::MissingRequiredSignature);
+        return Err(VaultError::MissingSignature.into());
+    }
+    Ok(())
+}
+
+// Ensure the signatures required by the vault's authority mode are present.
+// An owner signature refreshes the owner's last activity, so the caller must
+// save the record.
+fn authorize(
+    program_id: &Pubkey,
+    record: &mut VaultRecord,
+    dart: &AccountInfo,
+    owner: &AccountInfo,
+    signers: &[AccountInfo],
+) -> ProgramResult {
+    if record.authority_mode.dart_signs() {
+        validate_signer(program_id, dart, &record.dart, signers)?;
+    }
+    if record.authority_mode.owner_signs() {
+        validate_signer(program_id, owner, &record.owner, signers)?;
+    }
+    if record.authority_mode.owner_signs() || owner.is_signer {
+        record_activity(record)?;
+    }
+    Ok(())
+}
+
+// Refresh the owner's last activity, restarting the inactivity period after
+// which a beneficiary can claim the vault.
+fn record_activity(record: &mut VaultRecord) -> ProgramResult {
+    record.last_activity = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+// Ensure the signatures required by the vault's authority mode are present,
+// accepting an owner approval verified by the preceding ed25519 instruction in
+// place of the owner signature, when the instructions sysvar is passed among
+// the trailing accounts. The approval nonce is recorded, so the caller must
+// save the record.
+#[allow(clippy::too_many_arguments)]
+fn authorize_approved(
+    program_id: &Pubkey,
+    pda: &AccountInfo,
+    record: &mut VaultRecord,
+    dart: &AccountInfo,
+    owner: &AccountInfo,
+    signers: &[AccountInfo],
+    action: ApprovalAction,
+    destination: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let instructions = signers
+        .iter()
+        .find(|account| sysvar::instructions::check_id(account.key));
+    let instructions = match instructions {
+        Some(instructions) if record.authority_mode.owner_signs() && !owner.is_signer => {
+            instructions
+        }
+        _ => return authorize(program_id, record, dart, owner, signers),
+    };
+
+    if record.authority_mode.dart_signs() {
+        validate_signer(program_id, dart, &record.dart, signers)?;
+    }
+    let approval = OwnerApproval::load(instructions, &record.owner)?;
+    if approval.program_id != *program_id
+        || approval.vault != *pda.key
+        || approval.action != action
+        || approval.destination != *destination
+        || approval.amount != amount
+    {
+        msg!("Owner approval does not match the instruction");
+        return Err(VaultError::InvalidApproval.into());
+    }
+    if approval.nonce <= record.nonce {
+        msg!("Owner approval nonce {} already used", approval.nonce);
+        return Err(VaultError::StaleApproval.into());
+    }
+    record.nonce = approval.nonce;
+    record_activity(record)
+}
+
+// Ensure the vault is at the program address of its record, so it can sign via CPI.
+fn validate_program_address(
+    program_id: &Pubkey,
+    pda: &AccountInfo,
+    record: &VaultRecord,
+) -> ProgramResult {
+    let is_program_address = record.bump != 0
+        && Pubkey::create_program_address(&record.signer_seeds(), program_id)
+            .is_ok_and(|address| address == *pda.key);
+    if !is_program_address {
+        msg!("Vault not created at a program address");
+        return Err(VaultError::NotProgramAddress.into());
+    }
+    Ok(())
+}
+
+// Ensure the token program is SPL Token or Token-2022.
+fn validate_token_program(token_program: &AccountInfo) -> ProgramResult {
+    if *token_program.key != spl_token::id() && *token_program.key != spl_token_2022::id() {
+        msg!("invalid token program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+// Ensure the stake program is the native stake program.
+fn validate_stake_program(stake_program: &AccountInfo) -> ProgramResult {
+    if *stake_program.key != stake::program::id() {
+        msg!("invalid stake program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+// Ensure a token account is the associated token account of a wallet.
+fn validate_associated_token_account(
+    token_account: &AccountInfo,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> ProgramResult {
+    if *token_account.key
+        != get_associated_token_address_with_program_id(wallet, mint, token_program)
+    {
+        msg!("Not the associated token account of {}", wallet);
+        return Err(VaultError::IncorrectAccountKey.into());
+    }
+    Ok(())
+}
+
+// Load an initialized vault record from a program owned account.
+fn load_record(program_id: &Pubkey, pda: &AccountInfo) -> Result<VaultRecord, ProgramError> {
+    if pda.owner != program_id {
+        msg!("invalid program id");
+        return Err(VaultError::InvalidAccountOwner.into());
+    }
+    let record = VaultRecord::unpack(&pda.data.borrow())?;
+    if record.is_closed() {
+        msg!("vault record closed");
+        return Err(VaultError::VaultClosed.into());
+    }
+    if record.needs_migration() {
+        msg!("vault record requires migration");
+        return Err(VaultError::OutdatedRecord.into());
+    }
+    if !record.is_initialized() {
+        msg!("vault record not initialized");
+        return Err(VaultError::NotInitialized.into());
+    }
+    Ok(record)
+}
+
+// Wipe the account data of a closed vault, leaving only the tombstone version so
+// the account, kept rent-exempt, can never be revived or re-initialized.
+fn wipe_record(pda: &AccountInfo) {
+    let mut data = pda.data.borrow_mut();
+    data.fill(0);
+    if let Some(version) = data.first_mut() {
+        *version = VaultRecord::CLOSED_VERSION;
+    }
+}
+
+// Write a vault record back into account data.
+fn save_record(pda: &AccountInfo, record: &VaultRecord) -> ProgramResult {
+    borsh::to_writer(&mut pda.data.borrow_mut()[..], record).map_err(|e| e.into())
+}
+
+// Load the program config, ensuring it is the initialized singleton config account.
+fn load_config(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+    if config_info.owner != program_id {
+        msg!("invalid config program id");
+        return Err(VaultError::InvalidAccountOwner.into());
+    }
+    let config = ProgramConfig::unpack(&config_info.data.borrow())?;
+    if !config.is_initialized() {
+        msg!("program config not initialized");
+        return Err(VaultError::NotInitialized.into());
+    }
+    let expected = Pubkey::create_program_address(&[CONFIG_SEED, &[config.bump]], program_id)?;
+    if expected != *config_info.key {
+        msg!("Config account key mismatch");
+        return Err(VaultError::IncorrectAccountKey.into());
+    }
+    Ok(config)
+}
+
+// Load an initialized allowance drawing from the given vault.
+fn load_allowance(
+    program_id: &Pubkey,
+    allowance_info: &AccountInfo,
+    vault: &Pubkey,
+) -> Result<Allowance, ProgramError> {
+    if allowance_info.owner != program_id {
+        msg!("invalid allowance program id");
+        return Err(VaultError::InvalidAccountOwner.into());
+    }
+    let allowance = Allowance::unpack(&allowance_info.data.borrow())?;
+    if !allowance.is_initialized() {
+        msg!("allowance not initialized");
+        return Err(VaultError::NotInitialized.into());
+    }
+    if allowance.vault != *vault {
+        msg!("Allowance vault mismatch");
+        return Err(VaultError::IncorrectAccountKey.into());
+    }
+    Ok(allowance)
+}
+
+// Load an allowlist entry, ensuring it lists the destination for the given vault.
+fn load_destination(
+    program_id: &Pubkey,
+    entry_info: &AccountInfo,
+    vault: &Pubkey,
+    destination: &Pubkey,
+) -> Result<Destination, ProgramError> {
+    if entry_info.owner != program_id {
+        msg!("Destination {} not allowlisted", destination);
+        return Err(VaultError::DestinationNotAllowed.into());
+    }
+    let entry = Destination::unpack(&entry_info.data.borrow())?;
+    if !entry.is_initialized() || entry.vault != *vault || entry.destination != *destination {
+        msg!("Destination {} not allowlisted", destination);
+        return Err(VaultError::DestinationNotAllowed.into());
+    }
+    Ok(entry)
+}
+
+// Close a program owned account kept alongside a vault, moving its rent to the receiver.
+fn close_companion_account(account: &AccountInfo, receiver: &AccountInfo) -> ProgramResult {
+    let receiver_starting_lamports = receiver.lamports();
+    **receiver.lamports.borrow_mut() = receiver_starting_lamports
+        .checked_add(account.lamports())
+        .ok_or(VaultError::Overflow)?;
+    **account.lamports.borrow_mut() = 0;
+    account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+// Load the co-owner list of a vault, if the given owner account holds one.
+fn load_co_owners(
+    program_id: &Pubkey,
+    owner: &AccountInfo,
+    vault: &Pubkey,
+) -> Result<Option<CoOwners>, ProgramError> {
+    if owner.owner != program_id || owner.data_len() != CoOwners::LEN {
+        return Ok(None);
+    }
+    let co_owners = CoOwners::unpack(&owner.data.borrow())?;
+    if !co_owners.is_initialized() || co_owners.vault != *vault {
+        return Ok(None);
+    }
+    Ok(Some(co_owners))
+}
+
+// Write a co-owner list back into account data.
+fn save_co_owners(co_owners_info: &AccountInfo, co_owners: &CoOwners) -> ProgramResult {
+    borsh::to_writer(&mut co_owners_info.data.borrow_mut()[..], co_owners).map_err(|e| e.into())
+}
+
+// Load the metadata account of a vault.
+fn load_metadata(
+    program_id: &Pubkey,
+    metadata_info: &AccountInfo,
+    vault: &Pubkey,
+) -> Result<Metadata, ProgramError> {
+    if metadata_info.owner != program_id {
+        msg!("invalid metadata program id");
+        return Err(VaultError::InvalidAccountOwner.into());
+    }
+    let metadata = Metadata::unpack(&metadata_info.data.borrow())?;
+    if !metadata.is_initialized() {
+        msg!("metadata not initialized");
+        return Err(VaultError::NotInitialized.into());
+    }
+    if metadata.vault != *vault {
+        msg!("Metadata vault mismatch");
+        return Err(VaultError::IncorrectAccountKey.into());
+    }
+    Ok(metadata)
+}
+
+// Write an allowance back into account data.
+fn save_allowance(allowance_info: &AccountInfo, allowance: &Allowance) -> ProgramResult {
+    borsh::to_writer(&mut allowance_info.data.borrow_mut()[..], allowance).map_err(|e| e.into())
+}
+
+// Ensure the given key is an intermediary recognized by the program config.
+fn validate_intermediary(config: &ProgramConfig, key: &Pubkey) -> ProgramResult {
+    if !config.is_intermediary(key) {
+        msg!("Unknown intermediary: {}", key);
+        return Err(VaultError::UnknownIntermediary.into());
+    }
+    Ok(())
+}
+
+// Ensure the fee collector is the one configured in the program config.
+fn validate_fee_collector(config: &ProgramConfig, fee_collector: &AccountInfo) -> ProgramResult {
+    if config.fee_collector != *fee_collector.key {
+        msg!("Fee collector key mismatch");
+        return Err(VaultError::IncorrectAccountKey.into());
+    }
+    Ok(())
+}
+
+// Ensure ownership and funds may move, neither the vault nor the program being frozen.
+fn validate_not_frozen(config: &ProgramConfig, record: &VaultRecord) -> ProgramResult {
+    if config.paused {
+        msg!("Program is paused");
+        return Err(VaultError::Frozen.into());
+    }
+    if record.frozen {
+        msg!("Vault is frozen");
+        return Err(VaultError::Frozen.into());
+    }
+    Ok(())
+}
+
+// Ensure funds may leave the vault, any time lock having expired.
+fn validate_unlocked(record: &VaultRecord) -> ProgramResult {
+    if let Some(unlock_at) = record.unlock_at {
+        if !unlock_at.has_passed(&Clock::get()?) {
+            msg!("Vault locked until {:?}", unlock_at);
+            return Err(VaultError::Locked.into());
+        }
+    }
+    Ok(())
+}
+
+// Count a withdrawal against the vault's limit for the current window.
+fn validate_withdrawal_limit(record: &mut VaultRecord, amount: u64) -> ProgramResult {
+    if !record.record_withdrawal(amount, &Clock::get()?) {
+        msg!(
+            "Withdrawal exceeds limit: {} of {:?} withdrawn",
+            record.withdrawn,
+            record.limit
+        );
+        return Err(VaultError::WithdrawalLimitExceeded.into());
+    }
+    Ok(())
+}
+
+// Ensure the vault remains rent-exempt after paying out the given amount.
+fn validate_available(pda: &AccountInfo, amount: u64) -> ProgramResult {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(pda.data_len());
+    let available = pda.lamports().saturating_sub(rent_exempt_lamports);
+    if amount > available {
+        msg!("Withdrawal exceeds available balance: {}", available);
+        return Err(VaultError::InsufficientFunds.into());
+    }
+    Ok(())
+}
+
+// Ensure a list of intermediaries fits in the program config.
+fn validate_intermediaries(intermediaries: &[Pubkey]) -> ProgramResult {
+    if intermediaries.len() > ProgramConfig::MAX_INTERMEDIARIES {
+        msg!(
+            "Too many intermediaries (max {})",
+            ProgramConfig::MAX_INTERMEDIARIES
+        );
+        return Err(VaultError::TooManyIntermediaries.into());
+    }
+    Ok(())
+}
+
+// Ensure a co-owner list is within bounds, with positive shares, no duplicate
+// co-owners and a reachable threshold.
+fn validate_co_owners(owners: &[CoOwner], threshold: u8) -> ProgramResult {
+    if owners.is_empty() || owners.len() > CoOwners::MAX_OWNERS {
+        msg!(
+            "Invalid number of co-owners: {} (max {})",
+            owners.len(),
+            CoOwners::MAX_OWNERS
+        );
+        return Err(VaultError::InvalidCoOwners.into());
+    }
+    if threshold == 0 || usize::from(threshold) > owners.len() {
+        msg!("Invalid co-owner threshold: {}", threshold);
+        return Err(VaultError::InvalidCoOwners.into());
+    }
+    if owners.iter().any(|co_owner| co_owner.shares == 0) {
+        msg!("Co-owner shares must be positive");
+        return Err(VaultError::InvalidCoOwners.into());
+    }
+    let mut unique: Vec<_> = owners.iter().map(|co_owner| co_owner.owner).collect();
+    unique.sort();
+    unique.dedup();
+    if unique.len() != owners.len() {
+        msg!("Duplicate co-owners");
+        return Err(VaultError::InvalidCoOwners.into());
+    }
+    Ok(())
+}
+
+// Ensure a fee schedule charges at most the whole payout.
+fn validate_fee_schedule(fees: &FeeSchedule) -> ProgramResult {
+    if fees.basis_points > FeeSchedule::MAX_BASIS_POINTS {
+        msg!("Fee basis points exceed {}", FeeSchedule::MAX_BASIS_POINTS);
+        return Err(VaultError::InvalidFeeSchedule.into());
+    }
+    Ok(())
+}
+
+// Move lamports out of a vault, splitting them between the recipient and the fee collector.
+// Returns the fee taken.
+fn pay_out(
+    vault: &AccountInfo,
+    recipient: &AccountInfo,
+    fee_collector: &AccountInfo,
+    amount: u64,
+    fees: &FeeSchedule,
+) -> Result<u64, ProgramError> {
+    let fee = fees.fee_for(amount).ok_or(VaultError::Overflow)?;
+    let net = amount.checked_sub(fee).ok_or(VaultError::Overflow)?;
+
+    let vault_starting_lamports = vault.lamports();
+    **vault.lamports.borrow_mut() = vault_starting_lamports
+        .checked_sub(amount)
+        .ok_or(VaultError::Overflow)?;
+
+    let recipient_starting_lamports = recipient.lamports();
+    **recipient.lamports.borrow_mut() = recipient_starting_lamports
+        .checked_add(net)
+        .ok_or(VaultError::Overflow)?;
+
+    let collector_starting_lamports = fee_collector.lamports();
+    **fee_collector.lamports.borrow_mut() = collector_starting_lamports
+        .checked_add(fee)
+        .ok_or(VaultError::Overflow)?;
+
+    Ok(fee)
+}
+
+// Move lamports out of a co-owned vault, splitting them between the co-owner
+// wallets pro rata to their shares and the fee collector. Returns the fee taken.
+fn pay_out_co_owners(
+    vault: &AccountInfo,
+    co_owners: &CoOwners,
+    wallets: &[AccountInfo],
+    fee_collector: &AccountInfo,
+    amount: u64,
+    fees: &FeeSchedule,
+) -> Result<u64, ProgramError> {
+    let fee = fees.fee_for(amount).ok_or(VaultError::Overflow)?;
+    let net = amount.checked_sub(fee).ok_or(VaultError::Overflow)?;
+    let parts = co_owners.split(net).ok_or(VaultError::InvalidCoOwners)?;
+
+    let vault_starting_lamports = vault.lamports();
+    **vault.lamports.borrow_mut() = vault_starting_lamports
+        .checked_sub(amount)
+        .ok_or(VaultError::Overflow)?;
+
+    for (co_owner, part) in co_owners.owners.iter().zip(parts) {
+        let wallet = wallets
+            .iter()
+            .find(|wallet| *wallet.key == co_owner.owner)
+            .ok_or_else(|| {
+                msg!("Missing wallet of co-owner {}", co_owner.owner);
+                ProgramError::NotEnoughAccountKeys
+            })?;
+        let wallet_starting_lamports = wallet.lamports();
+        **wallet.lamports.borrow_mut() = wallet_starting_lamports
+            .checked_add(part)
+            .ok_or(VaultError::Overflow)?;
+    }
+
+    let collector_starting_lamports = fee_collector.lamports();
+    **fee_collector.lamports.borrow_mut() = collector_starting_lamports
+        .checked_add(fee)
+        .ok_or(VaultError::Overflow)?;
+
+    Ok(fee)
+}
+
+// Log a vault creation event.
+fn emit_created(pda: &AccountInfo, record: &VaultRecord) {
+    VaultEvent::Created {
+        vault: *pda.key,
+        owner: record.owner,
+        dart: record.dart,
+        version: record.version,
+    }
+    .emit();
+}
+
+// Log an ownership change event.
+fn emit_owner_changed(pda: &AccountInfo, previous_owner: &Pubkey, record: &VaultRecord) {
+    VaultEvent::OwnerChanged {
+        vault: *pda.key,
+        previous_owner: *previous_owner,
+        new_owner: record.owner,
+        version: record.version,
+    }
+    .emit();
+}
+
+// Log a withdrawal event.
+fn emit_withdrawn(
+    pda: &AccountInfo,
+    recipient: &AccountInfo,
+    amount: u64,
+    fee: u64,
+    record: &VaultRecord,
+) {
+    VaultEvent::Withdrawn {
+        vault: *pda.key,
+        recipient: *recipient.key,
+        amount,
+        fee,
+        version: record.version,
+    }
+    .emit();
+}
+
+// Log a config change event.
+fn emit_config_updated(config_info: &AccountInfo, config: ProgramConfig) {
+    VaultEvent::ConfigUpdated {
+        config: *config_info.key,
+        admin: config.admin,
+        fees: config.fees,
+        fee_collector: config.fee_collector,
+        paused: config.paused,
+        intermediaries: config.intermediaries,
+    }
+    .emit();
+}
+
+// Transfer lamports from the payer so the account is rent-exempt for the given space.
+fn fund_rent_exemption<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    space: usize,
+) -> ProgramResult {
+    let top_up = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program_info.clone()],
+        )?;
     }
     Ok(())
 }
 
+// Create a program owned account at a PDA, funding it to be rent exempt.
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    pda: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    program_id: &Pubkey,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if *system_program_info.key != system_program::id() {
+        msg!("invalid system program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if pda.lamports() == 0 {
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(space);
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                pda.key,
+                rent_exempt_lamports,
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), pda.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        );
+    }
+
+    // Lamports may have been sent to the address before creation.
+    fund_rent_exemption(payer, pda, system_program_info, space)?;
+    invoke_signed(
+        &system_instruction::allocate(pda.key, space as u64),
+        &[pda.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(pda.key, program_id),
+        &[pda.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )
+}
+
 /// Instruction processor
 pub struct Processor {}
 
@@ -34,9 +685,9 @@ impl Processor {
     ) -> ProgramResult {
         let instruction = VaultInstruction::try_from_slice(input)?;
         match instruction {
-            VaultInstruction::Initialize => {
+            VaultInstruction::Initialize { unlock_at } => {
                 msg!("VaultInstruction::Initialize");
-                Processor::process_initialize(program_id, accounts)
+                Processor::process_initialize(program_id, accounts, unlock_at)
             }
             VaultInstruction::TransferOwner => {
                 msg!("VaultInstruction::TransferOwner");
@@ -46,39 +697,239 @@ impl Processor {
                 msg!("VaultInstruction::CloseAccount");
                 Processor::close_account(program_id, accounts)
             }
+            VaultInstruction::Withdraw { amount } => {
+                msg!("VaultInstruction::Withdraw");
+                Processor::withdraw(program_id, accounts, amount)
+            }
+            VaultInstruction::CreateVault {
+                seed,
+                bump,
+                mode,
+                unlock_at,
+            } => {
+                msg!("VaultInstruction::CreateVault");
+                Processor::create_vault(program_id, accounts, &seed, bump, mode, unlock_at)
+            }
+            VaultInstruction::InitializeConfig { intermediaries } => {
+                msg!("VaultInstruction::InitializeConfig");
+                Processor::initialize_config(program_id, accounts, intermediaries)
+            }
+            VaultInstruction::UpdateConfig {
+                admin,
+                intermediaries,
+                fees,
+                fee_collector,
+                paused,
+            } => {
+                msg!("VaultInstruction::UpdateConfig");
+                Processor::update_config(
+                    program_id,
+                    accounts,
+                    admin,
+                    intermediaries,
+                    fees,
+                    fee_collector,
+                    paused,
+                )
+            }
+            VaultInstruction::ProposeOwner => {
+                msg!("VaultInstruction::ProposeOwner");
+                Processor::propose_owner(program_id, accounts)
+            }
+            VaultInstruction::AcceptOwner => {
+                msg!("VaultInstruction::AcceptOwner");
+                Processor::accept_owner(program_id, accounts)
+            }
+            VaultInstruction::CancelOwnerProposal => {
+                msg!("VaultInstruction::CancelOwnerProposal");
+                Processor::cancel_owner_proposal(program_id, accounts)
+            }
+            VaultInstruction::SetAuthorityMode { mode } => {
+                msg!("VaultInstruction::SetAuthorityMode");
+                Processor::set_authority_mode(program_id, accounts, mode)
+            }
+            VaultInstruction::Migrate => {
+                msg!("VaultInstruction::Migrate");
+                Processor::migrate(program_id, accounts)
+            }
+            VaultInstruction::Freeze => {
+                msg!("VaultInstruction::Freeze");
+                Processor::set_frozen(program_id, accounts, true)
+            }
+            VaultInstruction::Thaw => {
+                msg!("VaultInstruction::Thaw");
+                Processor::set_frozen(program_id, accounts, false)
+            }
+            VaultInstruction::ExtendLock { unlock_at } => {
+                msg!("VaultInstruction::ExtendLock");
+                Processor::extend_lock(program_id, accounts, unlock_at)
+            }
+            VaultInstruction::InitializeMultisig { threshold, signers } => {
+                msg!("VaultInstruction::InitializeMultisig");
+                Processor::initialize_multisig(program_id, accounts, threshold, signers)
+            }
+            VaultInstruction::RotateIntermediary => {
+                msg!("VaultInstruction::RotateIntermediary");
+                Processor::rotate_intermediary(program_id, accounts)
+            }
+            VaultInstruction::Approve {
+                delegate,
+                amount,
+                expires_at,
+                recipient,
+            } => {
+                msg!("VaultInstruction::Approve");
+                Processor::approve(
+                    program_id, accounts, delegate, amount, expires_at, recipient,
+                )
+            }
+            VaultInstruction::Revoke => {
+                msg!("VaultInstruction::Revoke");
+                Processor::revoke(program_id, accounts)
+            }
+            VaultInstruction::DelegatedWithdraw { amount } => {
+                msg!("VaultInstruction::DelegatedWithdraw");
+                Processor::delegated_withdraw(program_id, accounts, amount)
+            }
+            VaultInstruction::SetWithdrawalLimit { limit } => {
+                msg!("VaultInstruction::SetWithdrawalLimit");
+                Processor::set_withdrawal_limit(program_id, accounts, limit)
+            }
+            VaultInstruction::AddDestination { destination } => {
+                msg!("VaultInstruction::AddDestination");
+                Processor::add_destination(program_id, accounts, destination)
+            }
+            VaultInstruction::RemoveDestination => {
+                msg!("VaultInstruction::RemoveDestination");
+                Processor::remove_destination(program_id, accounts)
+            }
+            VaultInstruction::WithdrawTo {
+                destination,
+                amount,
+            } => {
+                msg!("VaultInstruction::WithdrawTo");
+                Processor::withdraw_to(program_id, accounts, destination, amount)
+            }
+            VaultInstruction::InternalTransfer { amount } => {
+                msg!("VaultInstruction::InternalTransfer");
+                Processor::internal_transfer(program_id, accounts, amount)
+            }
+            VaultInstruction::SetGuardian { guardian } => {
+                msg!("VaultInstruction::SetGuardian");
+                Processor::set_guardian(program_id, accounts, guardian)
+            }
+            VaultInstruction::StartRecovery { new_owner } => {
+                msg!("VaultInstruction::StartRecovery");
+                Processor::start_recovery(program_id, accounts, new_owner)
+            }
+            VaultInstruction::CancelRecovery => {
+                msg!("VaultInstruction::CancelRecovery");
+                Processor::cancel_recovery(program_id, accounts)
+            }
+            VaultInstruction::CompleteRecovery => {
+                msg!("VaultInstruction::CompleteRecovery");
+                Processor::complete_recovery(program_id, accounts)
+            }
+            VaultInstruction::OpenTokenAccount => {
+                msg!("VaultInstruction::OpenTokenAccount");
+                Processor::open_token_account(program_id, accounts)
+            }
+            VaultInstruction::WithdrawToken { amount } => {
+                msg!("VaultInstruction::WithdrawToken");
+                Processor::withdraw_token(program_id, accounts, amount)
+            }
+            VaultInstruction::CloseTokenAccount => {
+                msg!("VaultInstruction::CloseTokenAccount");
+                Processor::close_token_account(program_id, accounts)
+            }
+            VaultInstruction::CreateStake { index, amount } => {
+                msg!("VaultInstruction::CreateStake");
+                Processor::create_stake(program_id, accounts, index, amount)
+            }
+            VaultInstruction::DelegateStake => {
+                msg!("VaultInstruction::DelegateStake");
+                Processor::delegate_stake(program_id, accounts)
+            }
+            VaultInstruction::DeactivateStake => {
+                msg!("VaultInstruction::DeactivateStake");
+                Processor::deactivate_stake(program_id, accounts)
+            }
+            VaultInstruction::WithdrawStake => {
+                msg!("VaultInstruction::WithdrawStake");
+                Processor::withdraw_stake(program_id, accounts)
+            }
+            VaultInstruction::SetCoOwners { owners, threshold } => {
+                msg!("VaultInstruction::SetCoOwners");
+                Processor::set_co_owners(program_id, accounts, owners, threshold)
+            }
+            VaultInstruction::SetBeneficiary { inheritance } => {
+                msg!("VaultInstruction::SetBeneficiary");
+                Processor::set_beneficiary(program_id, accounts, inheritance)
+            }
+            VaultInstruction::ClaimInactive => {
+                msg!("VaultInstruction::ClaimInactive");
+                Processor::claim_inactive(program_id, accounts)
+            }
+            VaultInstruction::SetMetadata { metadata } => {
+                msg!("VaultInstruction::SetMetadata");
+                Processor::set_metadata(program_id, accounts, metadata)
+            }
+            VaultInstruction::ClearMetadata => {
+                msg!("VaultInstruction::ClearMetadata");
+                Processor::clear_metadata(program_id, accounts)
+            }
+            VaultInstruction::TransferShare => {
+                msg!("VaultInstruction::TransferShare");
+                Processor::transfer_share(program_id, accounts)
+            }
         }
     }
 
     // Initialize a vault record (by DART on behalf of a given owner).
-    fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    fn process_initialize(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        unlock_at: Option<UnlockAt>,
+    ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
         let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
         let dart = next_account_info(account_info_iter)?;
         let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
 
         // Check that the owner of the pda is the program.
         if pda.owner != program_id {
             msg!("invalid program id");
-            return Err(ProgramError::IncorrectProgramId);
+            return Err(VaultError::InvalidAccountOwner.into());
         }
 
-        if !dart.is_signer {
-            msg!("Missing required DART signature in initialize");
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        validate_signer(program_id, dart, dart.key, signers)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, dart.key)?;
 
-        let mut record = VaultRecord::try_from_slice(*pda.data.borrow())?;
-        if record.is_initialized() {
+        let mut record = VaultRecord::unpack(&pda.data.borrow())?;
+        if record.is_closed() {
+            msg!("Vault record account closed");
+            return Err(VaultError::VaultClosed.into());
+        }
+        // Only a zeroed account is uninitialized; older versions are migrated instead.
+        if record.version != 0 {
             msg!("Vault record account already initialized");
-            return Err(ProgramError::AccountAlreadyInitialized);
+            return Err(VaultError::AlreadyInitialized.into());
         }
 
         record.dart = *dart.key;
         record.owner = *owner.key;
+        record.unlock_at = unlock_at;
+        record.last_activity = Clock::get()?.unix_timestamp;
         record.version = VaultRecord::CURRENT_VERSION;
 
-        borsh::to_writer(&mut pda.data.borrow_mut()[..], &record).map_err(|e| e.into())
+        save_record(pda, &record)?;
+        emit_created(pda, &record);
+        Ok(())
     }
 
     // Transfer ownership of a vault record
@@ -86,70 +937,2187 @@ impl Processor {
         let account_info_iter = &mut accounts.iter();
 
         let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
         let dart = next_account_info(account_info_iter)?;
         let owner = next_account_info(account_info_iter)?;
         let new_owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
 
-        if pda.owner != program_id {
-            msg!("invalid program id");
-            return Err(ProgramError::IncorrectProgramId);
-        }
-
-        let mut record = VaultRecord::try_from_slice(&pda.data.borrow())?;
-        if !record.is_initialized() {
-            msg!("vault account not initialized");
-            return Err(ProgramError::UninitializedAccount);
-        }
+        let mut record = load_record(program_id, pda)?;
 
         // Ensure the current owner on record is correct.
         if record.owner != *owner.key {
-            return Err(ProgramError::IllegalOwner);
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // A co-owned vault changes hands one share at a time (see `TransferShare`).
+        if load_co_owners(program_id, owner, pda.key)?.is_some() {
+            msg!("Vault is co-owned; transfer a share instead");
+            return Err(VaultError::InvalidCoOwners.into());
         }
 
-        validate_signer(dart, &record.dart)?;
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
 
         record.owner = *new_owner.key;
+        record.pending_owner = None;
+        record.recovery = None;
+        record.inheritance = None;
 
-        borsh::to_writer(&mut pda.data.borrow_mut()[..], &record).map_err(|e| e.into())
+        save_record(pda, &record)?;
+        emit_owner_changed(pda, owner.key, &record);
+        Ok(())
     }
 
-    // Close a vault record account, draining lamports to the current owner.
-    fn close_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    // Transfer a co-owner's share of a co-owned vault.
+    fn transfer_share(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
 
         let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
         let dart = next_account_info(account_info_iter)?;
-        let owner = next_account_info(account_info_iter)?;
+        let co_owner = next_account_info(account_info_iter)?;
+        let new_co_owner = next_account_info(account_info_iter)?;
+        let co_owners_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
 
-        if pda.owner != program_id {
-            msg!("invalid program id");
-            return Err(ProgramError::IncorrectProgramId);
+        let record = load_record(program_id, pda)?;
+
+        // Ensure the co-owner is listed by the co-owner list on record.
+        if record.owner != *co_owners_info.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+        let mut co_owners =
+            load_co_owners(program_id, co_owners_info, pda.key)?.ok_or(VaultError::WrongOwner)?;
+        let Some(index) = co_owners.position(co_owner.key) else {
+            msg!("Not a co-owner: {}", co_owner.key);
+            return Err(VaultError::WrongOwner.into());
+        };
+
+        // The co-owner signs for their own share in place of the co-owners.
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        if record.authority_mode.dart_signs() {
+            validate_signer(program_id, dart, &record.dart, signers)?;
+        }
+        if record.authority_mode.owner_signs() {
+            validate_signer(program_id, co_owner, co_owner.key, signers)?;
+        }
+        validate_not_frozen(&config, &record)?;
+
+        if co_owners.position(new_co_owner.key).is_some() {
+            msg!("Already a co-owner: {}", new_co_owner.key);
+            return Err(VaultError::InvalidCoOwners.into());
         }
+        co_owners.owners[index].owner = *new_co_owner.key;
 
-        let record = VaultRecord::try_from_slice(&pda.data.borrow())?;
-        if !record.is_initialized() {
-            msg!("record not initialized");
-            return Err(ProgramError::UninitializedAccount);
+        save_co_owners(co_owners_info, &co_owners)?;
+        VaultEvent::ShareTransferred {
+            vault: *pda.key,
+            previous_owner: *co_owner.key,
+            new_owner: *new_co_owner.key,
+            shares: co_owners.owners[index].shares,
         }
+        .emit();
+        Ok(())
+    }
+
+    // Close a vault record account, paying its balance above rent to the current
+    // owner less fees.
+    fn close_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
 
-        // Ensure the intermediary signed off on the withdrawal
-        validate_signer(dart, &record.dart)?;
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let fee_collector = next_account_info(account_info_iter)?;
+        let metadata_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
 
         // Ensure the owner on record is correct.
         if record.owner != *owner.key {
-            return Err(ProgramError::IllegalOwner);
+            return Err(VaultError::WrongOwner.into());
         }
 
-        let owner_starting_lamports = owner.lamports();
-        let pda_lamports = pda.lamports();
+        // Ensure the required parties signed off on the withdrawal
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize_approved(
+            program_id,
+            pda,
+            &mut record,
+            dart,
+            owner,
+            signers,
+            ApprovalAction::CloseAccount,
+            owner.key,
+            0,
+        )?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
 
-        // TODO: Should DART get a fee?
+        // A closed vault can no longer sign for the accounts it is authority for.
+        if record.open_accounts > 0 {
+            msg!("Vault has {} open accounts", record.open_accounts);
+            return Err(VaultError::OpenAccounts.into());
+        }
 
-        **pda.lamports.borrow_mut() = 0;
-        **owner.lamports.borrow_mut() = owner_starting_lamports
-            .checked_add(pda_lamports)
-            .ok_or(VaultError::Overflow)?;
+        // The co-owner list of a co-owned vault is closed along with it, its
+        // rent paid out with the vault balance.
+        let co_owners = load_co_owners(program_id, owner, pda.key)?;
+        if co_owners.is_some() {
+            close_companion_account(owner, pda)?;
+        }
+
+        // So is the metadata account, if the vault has one.
+        let (metadata_address, _) =
+            Pubkey::find_program_address(&[METADATA_SEED_PREFIX, pda.key.as_ref()], program_id);
+        if metadata_address != *metadata_info.key {
+            msg!("Metadata account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+        let has_metadata = metadata_info.owner == program_id;
+        if has_metadata {
+            load_metadata(program_id, metadata_info, pda.key)?;
+            close_companion_account(metadata_info, pda)?;
+        }
+
+        // The tombstone keeps its rent-exempt minimum so it is never deleted.
+        let rent_exempt_lamports = Rent::get()?.minimum_balance(pda.data_len());
+        let amount = pda.lamports().saturating_sub(rent_exempt_lamports);
+        validate_withdrawal_limit(&mut record, amount)?;
+
+        validate_fee_collector(&config, fee_collector)?;
+
+        let fee = match &co_owners {
+            Some(co_owners) => {
+                pay_out_co_owners(pda, co_owners, signers, fee_collector, amount, &config.fees)?
+            }
+            None => pay_out(pda, owner, fee_collector, amount, &config.fees)?,
+        };
+        msg!("Close fee: {}", fee);
+
+        wipe_record(pda);
+        if has_metadata {
+            VaultEvent::MetadataChanged {
+                vault: *pda.key,
+                cleared: true,
+                version: VaultRecord::CLOSED_VERSION,
+            }
+            .emit();
+        }
+        VaultEvent::Closed {
+            vault: *pda.key,
+            owner: *owner.key,
+            amount,
+            fee,
+            version: VaultRecord::CLOSED_VERSION,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Withdraw part of a vault balance to the current owner, keeping the vault rent-exempt.
+    fn withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
 
-        borsh::to_writer(&mut pda.data.borrow_mut()[..], &record).map_err(|e| e.into())
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let fee_collector = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // Ensure the required parties signed off on the withdrawal
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize_approved(
+            program_id,
+            pda,
+            &mut record,
+            dart,
+            owner,
+            signers,
+            ApprovalAction::Withdraw,
+            owner.key,
+            amount,
+        )?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
+        validate_withdrawal_limit(&mut record, amount)?;
+
+        validate_available(pda, amount)?;
+        validate_fee_collector(&config, fee_collector)?;
+
+        let fee = match load_co_owners(program_id, owner, pda.key)? {
+            Some(co_owners) => pay_out_co_owners(
+                pda,
+                &co_owners,
+                signers,
+                fee_collector,
+                amount,
+                &config.fees,
+            )?,
+            None => pay_out(pda, owner, fee_collector, amount, &config.fees)?,
+        };
+        msg!("Withdraw fee: {}", fee);
+
+        save_record(pda, &record)?;
+        emit_withdrawn(pda, owner, amount, fee, &record);
+        Ok(())
+    }
+
+    // Create a vault at a program derived address and initialize its record.
+    fn create_vault(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seed: &str,
+        bump: u8,
+        mode: AuthorityMode,
+        unlock_at: Option<UnlockAt>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        validate_signer(program_id, dart, dart.key, signers)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, dart.key)?;
+
+        if seed.len() > MAX_SEED_LEN {
+            msg!("Vault seed exceeds {} bytes", MAX_SEED_LEN);
+            return Err(ProgramError::MaxSeedLengthExceeded);
+        }
+
+        // Ensure the vault address was derived from the DART and seed with the
+        // canonical bump, so each seed yields a single vault.
+        let address_seed = vault_seed(dart.key, seed);
+        let (expected, canonical_bump) =
+            Pubkey::find_program_address(&[VAULT_SEED_PREFIX, &address_seed], program_id);
+        if expected != *pda.key || bump != canonical_bump {
+            msg!("Vault address does not match derived address");
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let signer_seeds: &[&[u8]] = &[VAULT_SEED_PREFIX, &address_seed, &[bump]];
+
+        // A vault left behind as a tombstone must not be recreated.
+        if pda.owner == program_id {
+            if VaultRecord::unpack(&pda.data.borrow())?.is_closed() {
+                msg!("Vault address belongs to a closed vault");
+                return Err(VaultError::VaultClosed.into());
+            }
+            msg!("Vault account already initialized");
+            return Err(VaultError::AlreadyInitialized.into());
+        }
+
+        create_pda_account(
+            payer,
+            pda,
+            system_program_info,
+            program_id,
+            VaultRecord::LEN,
+            signer_seeds,
+        )?;
+
+        let record = VaultRecord {
+            version: VaultRecord::CURRENT_VERSION,
+            owner: *owner.key,
+            dart: *dart.key,
+            bump,
+            seed: address_seed,
+            pending_owner: None,
+            authority_mode: mode,
+            frozen: false,
+            unlock_at,
+            limit: None,
+            window_start: 0,
+            withdrawn: 0,
+            pending_limit: None,
+            nonce: 0,
+            guardian: None,
+            recovery: None,
+            inheritance: None,
+            last_activity: Clock::get()?.unix_timestamp,
+            open_accounts: 0,
+        };
+
+        save_record(pda, &record)?;
+        emit_created(pda, &record);
+        Ok(())
+    }
+
+    // Initialize the singleton program config.
+    fn initialize_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        intermediaries: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let config_info = next_account_info(account_info_iter)?;
+        let admin = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if !admin.is_signer {
+            msg!("Missing required admin signature in initialize config");
+            return Err(VaultError::MissingSignature.into());
+        }
+
+        validate_intermediaries(&intermediaries)?;
+
+        let (expected, bump) = Pubkey::find_program_address(&[CONFIG_SEED], program_id);
+        if expected != *config_info.key {
+            msg!("Config address does not match derived address");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if config_info.owner == program_id {
+            msg!("Program config already initialized");
+            return Err(VaultError::AlreadyInitialized.into());
+        }
+
+        create_pda_account(
+            payer,
+            config_info,
+            system_program_info,
+            program_id,
+            ProgramConfig::LEN,
+            &[CONFIG_SEED, &[bump]],
+        )?;
+
+        let config = ProgramConfig {
+            version: ProgramConfig::CURRENT_VERSION,
+            bump,
+            admin: *admin.key,
+            fees: FeeSchedule::default(),
+            fee_collector: *admin.key,
+            paused: false,
+            intermediaries,
+        };
+
+        borsh::to_writer(&mut config_info.data.borrow_mut()[..], &config)?;
+        emit_config_updated(config_info, config);
+        Ok(())
+    }
+
+    // Update the program config (by the config admin).
+    #[allow(clippy::too_many_arguments)]
+    fn update_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_admin: Option<Pubkey>,
+        intermediaries: Option<Vec<Pubkey>>,
+        fees: Option<FeeSchedule>,
+        fee_collector: Option<Pubkey>,
+        paused: Option<bool>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let config_info = next_account_info(account_info_iter)?;
+        let admin = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut config = load_config(program_id, config_info)?;
+        validate_signer(program_id, admin, &config.admin, signers)?;
+
+        if let Some(new_admin) = new_admin {
+            config.admin = new_admin;
+        }
+        if let Some(intermediaries) = intermediaries {
+            validate_intermediaries(&intermediaries)?;
+            config.intermediaries = intermediaries;
+        }
+        if let Some(fees) = fees {
+            validate_fee_schedule(&fees)?;
+            config.fees = fees;
+        }
+        if let Some(fee_collector) = fee_collector {
+            config.fee_collector = fee_collector;
+        }
+        if let Some(paused) = paused {
+            msg!("Program paused: {}", paused);
+            config.paused = paused;
+        }
+
+        borsh::to_writer(&mut config_info.data.borrow_mut()[..], &config)?;
+        emit_config_updated(config_info, config);
+        Ok(())
+    }
+
+    // Propose a new owner for a vault record, pending acceptance.
+    fn propose_owner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let new_owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        record.pending_owner = Some(*new_owner.key);
+
+        save_record(pda, &record)?;
+        VaultEvent::OwnerProposed {
+            vault: *pda.key,
+            proposed_owner: *new_owner.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Accept a pending ownership proposal (by the proposed owner).
+    fn accept_owner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let new_owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        validate_not_frozen(&config, &record)?;
+
+        let pending_owner = record.pending_owner.ok_or_else(|| {
+            msg!("No pending owner proposal");
+            ProgramError::from(VaultError::NoPendingOwner)
+        })?;
+        validate_signer(program_id, new_owner, &pending_owner, signers)?;
+        record_activity(&mut record)?;
+
+        let previous_owner = record.owner;
+        record.owner = pending_owner;
+        record.pending_owner = None;
+        record.recovery = None;
+        record.inheritance = None;
+
+        save_record(pda, &record)?;
+        emit_owner_changed(pda, &previous_owner, &record);
+        Ok(())
+    }
+
+    // Cancel a pending ownership proposal.
+    fn cancel_owner_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        if record.pending_owner.take().is_none() {
+            msg!("No pending owner proposal");
+            return Err(VaultError::NoPendingOwner.into());
+        }
+
+        save_record(pda, &record)?;
+        VaultEvent::OwnerProposalCancelled {
+            vault: *pda.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Change the signatures required by a vault (by DART and owner together).
+    fn set_authority_mode(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mode: AuthorityMode,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+
+        // Both parties must consent to a change in custody.
+        validate_signer(program_id, dart, &record.dart, signers)?;
+        validate_signer(program_id, owner, &record.owner, signers)?;
+        record_activity(&mut record)?;
+
+        record.authority_mode = mode;
+
+        save_record(pda, &record)?;
+        VaultEvent::AuthorityModeChanged {
+            vault: *pda.key,
+            mode,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Freeze or thaw a vault for a compliance hold (by the DART).
+    fn set_frozen(program_id: &Pubkey, accounts: &[AccountInfo], frozen: bool) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+
+        // Compliance holds are the DART's call, whatever the authority mode.
+        validate_signer(program_id, dart, &record.dart, signers)?;
+
+        record.frozen = frozen;
+
+        save_record(pda, &record)?;
+        VaultEvent::Frozen {
+            vault: *pda.key,
+            frozen,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Set or extend the time lock of a vault.
+    fn extend_lock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        unlock_at: UnlockAt,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        // A lock may only be pushed later, never shortened or switched to another measure.
+        if let Some(current) = record.unlock_at {
+            if !unlock_at.extends(&current) {
+                msg!("Time lock can only be extended: {:?}", current);
+                return Err(VaultError::InvalidLock.into());
+            }
+        }
+        record.unlock_at = Some(unlock_at);
+
+        save_record(pda, &record)?;
+        VaultEvent::LockExtended {
+            vault: *pda.key,
+            unlock_at,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Initialize an M-of-N multisig authority.
+    fn initialize_multisig(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        threshold: u8,
+        signers: Vec<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let multisig_info = next_account_info(account_info_iter)?;
+
+        if multisig_info.owner != program_id {
+            msg!("invalid program id");
+            return Err(VaultError::InvalidAccountOwner.into());
+        }
+
+        // The account size is what tells a multisig apart from other program accounts.
+        if multisig_info.data_len() != Multisig::LEN {
+            msg!("Multisig account must be {} bytes", Multisig::LEN);
+            return Err(VaultError::InvalidMultisig.into());
+        }
+
+        if Multisig::unpack(&multisig_info.data.borrow())?.version != 0 {
+            msg!("Multisig account already initialized");
+            return Err(VaultError::AlreadyInitialized.into());
+        }
+
+        if signers.len() > Multisig::MAX_SIGNERS {
+            msg!("Too many multisig signers (max {})", Multisig::MAX_SIGNERS);
+            return Err(VaultError::InvalidMultisig.into());
+        }
+        if threshold == 0 || usize::from(threshold) > signers.len() {
+            msg!("Invalid multisig threshold: {}", threshold);
+            return Err(VaultError::InvalidMultisig.into());
+        }
+        let mut unique = signers.clone();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != signers.len() {
+            msg!("Duplicate multisig signers");
+            return Err(VaultError::InvalidMultisig.into());
+        }
+
+        let multisig = Multisig {
+            version: Multisig::CURRENT_VERSION,
+            threshold,
+            signers,
+        };
+
+        borsh::to_writer(&mut multisig_info.data.borrow_mut()[..], &multisig)?;
+        VaultEvent::MultisigInitialized {
+            multisig: *multisig_info.key,
+            threshold,
+            signers: multisig.signers,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Move a vault to a new intermediary (by the current DART or the config admin).
+    fn rotate_intermediary(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+        let new_dart = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+        let config = load_config(program_id, config_info)?;
+
+        // The admin can rotate away from a DART that is lost or no longer recognized.
+        if *authority.key == config.admin {
+            validate_signer(program_id, authority, &config.admin, signers)?;
+        } else {
+            validate_signer(program_id, authority, &record.dart, signers)?;
+        }
+        validate_intermediary(&config, new_dart.key)?;
+
+        msg!("Rotating intermediary {} to {}", record.dart, new_dart.key);
+        let previous_dart = record.dart;
+        record.dart = *new_dart.key;
+
+        save_record(pda, &record)?;
+        VaultEvent::IntermediaryRotated {
+            vault: *pda.key,
+            previous_dart,
+            new_dart: record.dart,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Create or replace the allowance of a delegate on a vault.
+    fn approve(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+        amount: u64,
+        expires_at: Option<UnixTimestamp>,
+        recipient: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let allowance_info = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        let (expected, bump) = Pubkey::find_program_address(
+            &[ALLOWANCE_SEED_PREFIX, pda.key.as_ref(), delegate.as_ref()],
+            program_id,
+        );
+        if expected != *allowance_info.key {
+            msg!("Allowance account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        let allowance = Allowance {
+            version: Allowance::CURRENT_VERSION,
+            bump,
+            vault: *pda.key,
+            delegate,
+            amount,
+            expires_at,
+            owner: record.owner,
+            recipient,
+        };
+        if allowance_info.owner != program_id {
+            create_pda_account(
+                payer,
+                allowance_info,
+                system_program_info,
+                program_id,
+                Allowance::LEN,
+                &allowance.signer_seeds(),
+            )?;
+        }
+
+        msg!("Approved {} for {} lamports", delegate, amount);
+        save_record(pda, &record)?;
+        save_allowance(allowance_info, &allowance)?;
+        VaultEvent::AllowanceApproved {
+            vault: *pda.key,
+            delegate,
+            recipient,
+            amount,
+            expires_at,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Revoke a delegate's allowance, returning its rent to the receiver.
+    fn revoke(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let allowance_info = next_account_info(account_info_iter)?;
+        let receiver = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        let allowance = load_allowance(program_id, allowance_info, pda.key)?;
+        msg!("Revoking allowance of {}", allowance.delegate);
+
+        save_record(pda, &record)?;
+        close_companion_account(allowance_info, receiver)?;
+        VaultEvent::AllowanceRevoked {
+            vault: *pda.key,
+            delegate: allowance.delegate,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Withdraw from a vault on behalf of the owner, drawing down a delegate's allowance.
+    fn delegated_withdraw(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let delegate = next_account_info(account_info_iter)?;
+        let allowance_info = next_account_info(account_info_iter)?;
+        let recipient = next_account_info(account_info_iter)?;
+        let fee_collector = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+
+        let mut allowance = load_allowance(program_id, allowance_info, pda.key)?;
+        validate_signer(program_id, delegate, &allowance.delegate, signers)?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
+
+        // Allowances lapse once the vault changes hands.
+        if allowance.owner != record.owner {
+            msg!("Allowance granted by previous owner {}", allowance.owner);
+            return Err(VaultError::OwnerChanged.into());
+        }
+        if allowance.recipient != *recipient.key {
+            msg!("Allowance only pays out to {}", allowance.recipient);
+            return Err(VaultError::WrongRecipient.into());
+        }
+
+        if allowance.has_expired(&Clock::get()?) {
+            msg!("Allowance expired at {:?}", allowance.expires_at);
+            return Err(VaultError::AllowanceExpired.into());
+        }
+        allowance.amount = allowance.amount.checked_sub(amount).ok_or_else(|| {
+            msg!("Withdrawal exceeds allowance: {}", allowance.amount);
+            VaultError::AllowanceExceeded
+        })?;
+        validate_withdrawal_limit(&mut record, amount)?;
+
+        validate_available(pda, amount)?;
+        validate_fee_collector(&config, fee_collector)?;
+
+        let fee = pay_out(pda, recipient, fee_collector, amount, &config.fees)?;
+        msg!("Delegated withdraw fee: {}", fee);
+
+        save_record(pda, &record)?;
+        save_allowance(allowance_info, &allowance)?;
+        emit_withdrawn(pda, recipient, amount, fee, &record);
+        Ok(())
+    }
+
+    // Set the withdrawal limit of a vault. Tightening the limit applies at once,
+    // by the DART or owner; raising it is left pending until the raise delay has
+    // passed, and only the DART may do so.
+    fn set_withdrawal_limit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        limit: Option<WithdrawalLimit>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let authority = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+
+        let by_dart = *authority.key != record.owner;
+        if by_dart {
+            validate_signer(program_id, authority, &record.dart, signers)?;
+        } else {
+            validate_signer(program_id, authority, &record.owner, signers)?;
+            record_activity(&mut record)?;
+        }
+
+        if let Some(limit) = limit {
+            if limit.window <= 0 {
+                msg!("Withdrawal limit window must be positive");
+                return Err(VaultError::InvalidLimit.into());
+            }
+        }
+
+        let clock = Clock::get()?;
+        record.settle_limit(&clock);
+        let effective_at = if WithdrawalLimit::is_within(&limit, &record.limit) {
+            msg!("Withdrawal limit set to {:?}", limit);
+            record.limit = limit;
+            record.pending_limit = None;
+            clock.unix_timestamp
+        } else {
+            if !by_dart {
+                msg!("Only the DART can raise the withdrawal limit");
+                return Err(VaultError::MissingSignature.into());
+            }
+            let effective_at = clock
+                .unix_timestamp
+                .checked_add(WithdrawalLimit::RAISE_DELAY)
+                .ok_or(VaultError::Overflow)?;
+            msg!(
+                "Withdrawal limit {:?} pending until {}",
+                limit,
+                effective_at
+            );
+            record.pending_limit = Some(PendingLimit {
+                limit,
+                effective_at,
+            });
+            effective_at
+        };
+
+        save_record(pda, &record)?;
+        VaultEvent::LimitChanged {
+            vault: *pda.key,
+            limit,
+            effective_at,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Allowlist a withdrawal destination for a vault (by owner plus DART), usable
+    // once the cooling-off period has passed.
+    fn add_destination(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        destination: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let entry_info = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        validate_signer(program_id, dart, &record.dart, signers)?;
+        validate_signer(program_id, owner, &record.owner, signers)?;
+        record_activity(&mut record)?;
+        save_record(pda, &record)?;
+
+        let (expected, bump) = Pubkey::find_program_address(
+            &[
+                DESTINATION_SEED_PREFIX,
+                pda.key.as_ref(),
+                destination.as_ref(),
+            ],
+            program_id,
+        );
+        if expected != *entry_info.key {
+            msg!("Destination account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        let entry = Destination {
+            version: Destination::CURRENT_VERSION,
+            bump,
+            vault: *pda.key,
+            destination,
+            active_at: Clock::get()?
+                .unix_timestamp
+                .checked_add(Destination::COOLING_OFF)
+                .ok_or(VaultError::Overflow)?,
+            owner: record.owner,
+        };
+        if entry_info.owner == program_id {
+            // An entry added by a previous owner has lapsed and is replaced.
+            let existing = Destination::unpack(&entry_info.data.borrow())?;
+            if existing.is_initialized() && existing.owner == record.owner {
+                msg!("Destination already allowlisted");
+                return Err(VaultError::AlreadyInitialized.into());
+            }
+        } else {
+            create_pda_account(
+                payer,
+                entry_info,
+                system_program_info,
+                program_id,
+                Destination::LEN,
+                &entry.signer_seeds(),
+            )?;
+        }
+
+        msg!("Allowlisted {} from {}", destination, entry.active_at);
+        borsh::to_writer(&mut entry_info.data.borrow_mut()[..], &entry)?;
+        VaultEvent::DestinationAdded {
+            vault: *pda.key,
+            destination,
+            active_at: entry.active_at,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Remove a withdrawal destination from a vault allowlist (by owner plus DART).
+    fn remove_destination(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let entry_info = next_account_info(account_info_iter)?;
+        let receiver = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        validate_signer(program_id, dart, &record.dart, signers)?;
+        validate_signer(program_id, owner, &record.owner, signers)?;
+        record_activity(&mut record)?;
+        save_record(pda, &record)?;
+
+        if entry_info.owner != program_id {
+            msg!("invalid destination program id");
+            return Err(VaultError::InvalidAccountOwner.into());
+        }
+        let entry = Destination::unpack(&entry_info.data.borrow())?;
+        if !entry.is_initialized() || entry.vault != *pda.key {
+            msg!("Destination vault mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+        msg!("Removing destination {}", entry.destination);
+
+        close_companion_account(entry_info, receiver)?;
+        VaultEvent::DestinationRemoved {
+            vault: *pda.key,
+            destination: entry.destination,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Withdraw part of a vault balance to an allowlisted destination.
+    fn withdraw_to(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        destination: Pubkey,
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let entry_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let fee_collector = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // Ensure the required parties signed off on the withdrawal
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize_approved(
+            program_id,
+            pda,
+            &mut record,
+            dart,
+            owner,
+            signers,
+            ApprovalAction::WithdrawTo,
+            destination_info.key,
+            amount,
+        )?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
+
+        if *destination_info.key != destination {
+            msg!("Destination account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+        let entry = load_destination(program_id, entry_info, pda.key, &destination)?;
+        // Allowlist entries lapse once the vault changes hands.
+        if entry.owner != record.owner {
+            msg!("Destination allowlisted by previous owner {}", entry.owner);
+            return Err(VaultError::OwnerChanged.into());
+        }
+        if !entry.is_active(&Clock::get()?) {
+            msg!("Destination cooling off until {}", entry.active_at);
+            return Err(VaultError::DestinationCoolingOff.into());
+        }
+        validate_withdrawal_limit(&mut record, amount)?;
+
+        validate_available(pda, amount)?;
+        validate_fee_collector(&config, fee_collector)?;
+
+        let fee = pay_out(pda, destination_info, fee_collector, amount, &config.fees)?;
+        msg!("Withdraw fee: {}", fee);
+
+        save_record(pda, &record)?;
+        emit_withdrawn(pda, destination_info, amount, fee, &record);
+        Ok(())
+    }
+
+    // Move lamports between two vaults of the same DART, keeping the source rent-exempt.
+    fn internal_transfer(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let from = next_account_info(account_info_iter)?;
+        let to = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, from)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // Ensure the required parties signed off on the transfer
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
+
+        // Funds stay with the same custodian.
+        if from.key == to.key {
+            msg!("Cannot transfer a vault to itself");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+        let destination = load_record(program_id, to)?;
+        if destination.dart != record.dart {
+            msg!("Destination vault held by another intermediary");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        validate_withdrawal_limit(&mut record, amount)?;
+        validate_available(from, amount)?;
+
+        let from_starting_lamports = from.lamports();
+        **from.lamports.borrow_mut() = from_starting_lamports
+            .checked_sub(amount)
+            .ok_or(VaultError::Overflow)?;
+        let to_starting_lamports = to.lamports();
+        **to.lamports.borrow_mut() = to_starting_lamports
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        save_record(from, &record)?;
+        VaultEvent::Transferred {
+            from: *from.key,
+            to: *to.key,
+            amount,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Set or remove the guardian able to recover a vault, cancelling any pending recovery.
+    fn set_guardian(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        guardian: Option<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        record.guardian = guardian;
+        record.recovery = None;
+
+        save_record(pda, &record)?;
+        VaultEvent::GuardianChanged {
+            vault: *pda.key,
+            guardian,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Start recovering a vault to a new owner, once the challenge period is over.
+    fn start_recovery(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_owner: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let guardian = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        let Some(guardian_key) = record.guardian else {
+            msg!("Vault has no guardian");
+            return Err(VaultError::IncorrectAccountKey.into());
+        };
+        validate_signer(program_id, guardian, &guardian_key, signers)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        validate_not_frozen(&config, &record)?;
+
+        // A pending recovery cannot be repointed without the owner seeing a
+        // new challenge period.
+        if let Some(recovery) = &record.recovery {
+            msg!("Recovery to {} already pending", recovery.new_owner);
+            return Err(VaultError::RecoveryPending.into());
+        }
+
+        let completes_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(Recovery::CHALLENGE_PERIOD)
+            .ok_or(VaultError::Overflow)?;
+        msg!("Recovery to {} completes at {}", new_owner, completes_at);
+        record.recovery = Some(Recovery {
+            new_owner,
+            completes_at,
+        });
+
+        save_record(pda, &record)?;
+        VaultEvent::RecoveryStarted {
+            vault: *pda.key,
+            new_owner,
+            completes_at,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Cancel a pending owner recovery.
+    fn cancel_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+
+        // The owner alone can always stop a recovery, whatever the authority mode.
+        if validate_signer(program_id, owner, &record.owner, signers).is_ok() {
+            record_activity(&mut record)?;
+        } else {
+            authorize(program_id, &mut record, dart, owner, signers)?;
+        }
+
+        if record.recovery.take().is_none() {
+            msg!("No pending recovery");
+            return Err(VaultError::NoPendingRecovery.into());
+        }
+
+        save_record(pda, &record)?;
+        VaultEvent::RecoveryCancelled {
+            vault: *pda.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Complete an owner recovery whose challenge period is over.
+    fn complete_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+
+        let mut record = load_record(program_id, pda)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_not_frozen(&config, &record)?;
+
+        let recovery = record.recovery.ok_or_else(|| {
+            msg!("No pending recovery");
+            ProgramError::from(VaultError::NoPendingRecovery)
+        })?;
+        if !recovery.can_complete(&Clock::get()?) {
+            msg!("Recovery completes at {}", recovery.completes_at);
+            return Err(VaultError::RecoveryNotReady.into());
+        }
+
+        let previous_owner = record.owner;
+        record.owner = recovery.new_owner;
+        record.pending_owner = None;
+        record.recovery = None;
+        record.inheritance = None;
+
+        save_record(pda, &record)?;
+        emit_owner_changed(pda, &previous_owner, &record);
+        Ok(())
+    }
+
+    // Open the associated token account of a vault for a mint.
+    fn open_token_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let payer = next_account_info(account_info_iter)?;
+        let pda = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
+
+        let mut record = load_record(program_id, pda)?;
+        validate_program_address(program_id, pda, &record)?;
+        validate_token_program(token_program)?;
+
+        if *associated_token_program.key != spl_associated_token_account::id() {
+            msg!("invalid associated token program id");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // Opening is idempotent; only a newly created account is counted.
+        let created = token_account.data_is_empty();
+
+        // The associated token program checks the token account address.
+        invoke(
+            &create_associated_token_account_idempotent(
+                payer.key,
+                pda.key,
+                mint.key,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                token_account.clone(),
+                pda.clone(),
+                mint.clone(),
+                system_program_info.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+
+        if created {
+            record.open_accounts = record
+                .open_accounts
+                .checked_add(1)
+                .ok_or(VaultError::Overflow)?;
+        }
+        save_record(pda, &record)?;
+        VaultEvent::TokenAccountOpened {
+            vault: *pda.key,
+            mint: *mint.key,
+            token_account: *token_account.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Withdraw tokens from a vault token account to the owner's token account.
+    fn withdraw_token(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // Ensure the required parties signed off on the withdrawal
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
+        validate_unlocked(&record)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_token_program(token_program)?;
+        validate_associated_token_account(token_account, pda.key, mint.key, token_program.key)?;
+        validate_associated_token_account(
+            owner_token_account,
+            owner.key,
+            mint.key,
+            token_program.key,
+        )?;
+
+        // The token program checks the mint matches both token accounts.
+        let decimals = StateWithExtensions::<Mint>::unpack(&mint.data.borrow())?
+            .base
+            .decimals;
+        invoke_signed(
+            &spl_token_2022::instruction::transfer_checked(
+                token_program.key,
+                token_account.key,
+                mint.key,
+                owner_token_account.key,
+                pda.key,
+                &[],
+                amount,
+                decimals,
+            )?,
+            &[
+                token_account.clone(),
+                mint.clone(),
+                owner_token_account.clone(),
+                pda.clone(),
+                token_program.clone(),
+            ],
+            &[&record.signer_seeds()],
+        )?;
+
+        save_record(pda, &record)?;
+        VaultEvent::TokenWithdrawn {
+            vault: *pda.key,
+            mint: *mint.key,
+            recipient: *owner_token_account.key,
+            amount,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Close an empty vault token account, returning its rent to the vault.
+    fn close_token_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_token_program(token_program)?;
+
+        // The token program checks the vault owns the token account and that it is empty.
+        invoke_signed(
+            &spl_token_2022::instruction::close_account(
+                token_program.key,
+                token_account.key,
+                pda.key,
+                pda.key,
+                &[],
+            )?,
+            &[token_account.clone(), pda.clone(), token_program.clone()],
+            &[&record.signer_seeds()],
+        )?;
+
+        // Token accounts created outside the vault program were never counted.
+        record.open_accounts = record.open_accounts.saturating_sub(1);
+
+        save_record(pda, &record)?;
+        VaultEvent::TokenAccountClosed {
+            vault: *pda.key,
+            token_account: *token_account.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Create a stake account funded from a vault, with the vault as its authority.
+    fn create_stake(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u64,
+        amount: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let payer = next_account_info(account_info_iter)?;
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let stake_info = next_account_info(account_info_iter)?;
+        let rent_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let stake_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        // Ensure the required parties signed off on staking
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_stake_program(stake_program)?;
+
+        let index_bytes = index.to_le_bytes();
+        let (expected, bump) = Pubkey::find_program_address(
+            &[STAKE_SEED_PREFIX, pda.key.as_ref(), &index_bytes],
+            program_id,
+        );
+        if expected != *stake_info.key {
+            msg!("Stake account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        validate_available(pda, amount)?;
+
+        create_pda_account(
+            payer,
+            stake_info,
+            system_program_info,
+            stake_program.key,
+            StakeStateV2::size_of(),
+            &[STAKE_SEED_PREFIX, pda.key.as_ref(), &index_bytes, &[bump]],
+        )?;
+
+        let authorized = Authorized {
+            staker: *pda.key,
+            withdrawer: *pda.key,
+        };
+        invoke(
+            &stake::instruction::initialize(stake_info.key, &authorized, &Lockup::default()),
+            &[stake_info.clone(), rent_info.clone(), stake_program.clone()],
+        )?;
+
+        // Fund the initialized stake account from the vault.
+        let vault_starting_lamports = pda.lamports();
+        **pda.lamports.borrow_mut() = vault_starting_lamports
+            .checked_sub(amount)
+            .ok_or(VaultError::Overflow)?;
+        let stake_starting_lamports = stake_info.lamports();
+        **stake_info.lamports.borrow_mut() = stake_starting_lamports
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+
+        record.open_accounts = record
+            .open_accounts
+            .checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+
+        save_record(pda, &record)?;
+        VaultEvent::StakeCreated {
+            vault: *pda.key,
+            stake: *stake_info.key,
+            amount,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Delegate a vault stake account to a validator.
+    fn delegate_stake(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let stake_info = next_account_info(account_info_iter)?;
+        let vote_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let stake_history_info = next_account_info(account_info_iter)?;
+        let stake_config_info = next_account_info(account_info_iter)?;
+        let stake_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_stake_program(stake_program)?;
+
+        // The stake program checks the vault is the stake authority.
+        invoke_signed(
+            &stake::instruction::delegate_stake(stake_info.key, pda.key, vote_info.key),
+            &[
+                stake_info.clone(),
+                vote_info.clone(),
+                clock_info.clone(),
+                stake_history_info.clone(),
+                stake_config_info.clone(),
+                pda.clone(),
+                stake_program.clone(),
+            ],
+            &[&record.signer_seeds()],
+        )?;
+
+        save_record(pda, &record)?;
+        VaultEvent::StakeDelegated {
+            vault: *pda.key,
+            stake: *stake_info.key,
+            vote: *vote_info.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Deactivate a vault stake account.
+    fn deactivate_stake(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let stake_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let stake_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_stake_program(stake_program)?;
+
+        // The stake program checks the vault is the stake authority.
+        invoke_signed(
+            &stake::instruction::deactivate_stake(stake_info.key, pda.key),
+            &[
+                stake_info.clone(),
+                clock_info.clone(),
+                pda.clone(),
+                stake_program.clone(),
+            ],
+            &[&record.signer_seeds()],
+        )?;
+
+        save_record(pda, &record)?;
+        VaultEvent::StakeDeactivated {
+            vault: *pda.key,
+            stake: *stake_info.key,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Withdraw an inactive vault stake account back into the vault.
+    fn withdraw_stake(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let stake_info = next_account_info(account_info_iter)?;
+        let clock_info = next_account_info(account_info_iter)?;
+        let stake_history_info = next_account_info(account_info_iter)?;
+        let stake_program = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        validate_program_address(program_id, pda, &record)?;
+        validate_stake_program(stake_program)?;
+
+        // The stake program checks the vault is the withdraw authority and
+        // that the stake is inactive.
+        let amount = stake_info.lamports();
+        invoke_signed(
+            &stake::instruction::withdraw(stake_info.key, pda.key, pda.key, amount, None),
+            &[
+                stake_info.clone(),
+                pda.clone(),
+                clock_info.clone(),
+                stake_history_info.clone(),
+                stake_program.clone(),
+            ],
+            &[&record.signer_seeds()],
+        )?;
+
+        // Withdrawing the whole balance closes the stake account. Stake accounts
+        // created outside the vault program were never counted.
+        record.open_accounts = record.open_accounts.saturating_sub(1);
+
+        save_record(pda, &record)?;
+        VaultEvent::StakeWithdrawn {
+            vault: *pda.key,
+            stake: *stake_info.key,
+            amount,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Make a vault co-owned, or replace its co-owners.
+    fn set_co_owners(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owners: Vec<CoOwner>,
+        threshold: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let payer = next_account_info(account_info_iter)?;
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let co_owners_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+        validate_not_frozen(&config, &record)?;
+
+        validate_co_owners(&owners, threshold)?;
+
+        let (expected, bump) =
+            Pubkey::find_program_address(&[CO_OWNERS_SEED_PREFIX, pda.key.as_ref()], program_id);
+        if expected != *co_owners_info.key {
+            msg!("Co-owner list account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        let co_owners = CoOwners {
+            version: CoOwners::CURRENT_VERSION,
+            bump,
+            vault: *pda.key,
+            threshold,
+            owners,
+        };
+        if co_owners_info.owner != program_id {
+            create_pda_account(
+                payer,
+                co_owners_info,
+                system_program_info,
+                program_id,
+                CoOwners::LEN,
+                &co_owners.signer_seeds(),
+            )?;
+        }
+        save_co_owners(co_owners_info, &co_owners)?;
+        msg!(
+            "Co-owners: {}, threshold: {}",
+            co_owners.owners.len(),
+            threshold
+        );
+
+        if record.owner != *co_owners_info.key {
+            let previous_owner = record.owner;
+            record.owner = *co_owners_info.key;
+            record.pending_owner = None;
+            record.recovery = None;
+            record.inheritance = None;
+
+            save_record(pda, &record)?;
+            emit_owner_changed(pda, &previous_owner, &record);
+        } else {
+            save_record(pda, &record)?;
+        }
+        VaultEvent::CoOwnersChanged {
+            vault: *pda.key,
+            owners: co_owners.owners,
+            threshold,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Set or remove the beneficiary able to claim an inactive vault, restarting
+    // the inactivity period.
+    fn set_beneficiary(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        inheritance: Option<Inheritance>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the current owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        if let Some(inheritance) = inheritance {
+            if inheritance.inactivity_period <= 0 {
+                msg!("Inactivity period must be positive");
+                return Err(VaultError::InvalidInactivityPeriod.into());
+            }
+        }
+
+        record.inheritance = inheritance;
+        record_activity(&mut record)?;
+
+        save_record(pda, &record)?;
+        VaultEvent::BeneficiaryChanged {
+            vault: *pda.key,
+            inheritance,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Hand a vault over to its beneficiary once the owner has been inactive
+    // for the inactivity period.
+    fn claim_inactive(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let beneficiary = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        let Some(inheritance) = record.inheritance else {
+            msg!("Vault has no beneficiary");
+            return Err(VaultError::NoBeneficiary.into());
+        };
+        validate_signer(program_id, beneficiary, &inheritance.beneficiary, signers)?;
+
+        let config = load_config(program_id, config_info)?;
+        validate_not_frozen(&config, &record)?;
+
+        if !inheritance.can_claim(record.last_activity, &Clock::get()?) {
+            msg!(
+                "Owner last active at {}, inactivity period {}",
+                record.last_activity,
+                inheritance.inactivity_period
+            );
+            return Err(VaultError::OwnerNotInactive.into());
+        }
+
+        let previous_owner = record.owner;
+        record.owner = inheritance.beneficiary;
+        record.pending_owner = None;
+        record.recovery = None;
+        record.inheritance = None;
+        record_activity(&mut record)?;
+
+        save_record(pda, &record)?;
+        emit_owner_changed(pda, &previous_owner, &record);
+        Ok(())
+    }
+
+    // Attach metadata to a vault, creating its metadata account on first use.
+    fn set_metadata(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        metadata: VaultMetadata,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let payer = next_account_info(account_info_iter)?;
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let metadata_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        let len = metadata.packed_len();
+        if len > VaultMetadata::MAX_LEN {
+            msg!(
+                "Metadata of {} bytes exceeds {}",
+                len,
+                VaultMetadata::MAX_LEN
+            );
+            return Err(VaultError::MetadataTooLarge.into());
+        }
+
+        let (expected, bump) =
+            Pubkey::find_program_address(&[METADATA_SEED_PREFIX, pda.key.as_ref()], program_id);
+        if expected != *metadata_info.key {
+            msg!("Metadata account key mismatch");
+            return Err(VaultError::IncorrectAccountKey.into());
+        }
+
+        let metadata = Metadata {
+            version: Metadata::CURRENT_VERSION,
+            bump,
+            vault: *pda.key,
+            metadata,
+        };
+        if metadata_info.owner != program_id {
+            create_pda_account(
+                payer,
+                metadata_info,
+                system_program_info,
+                program_id,
+                Metadata::LEN,
+                &metadata.signer_seeds(),
+            )?;
+        }
+
+        save_record(pda, &record)?;
+        // Clear the tail of longer metadata being replaced.
+        let mut data = metadata_info.data.borrow_mut();
+        data.fill(0);
+        borsh::to_writer(&mut data[..], &metadata)?;
+        VaultEvent::MetadataChanged {
+            vault: *pda.key,
+            cleared: false,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Remove the metadata of a vault, closing its metadata account.
+    fn clear_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let config_info = next_account_info(account_info_iter)?;
+        let dart = next_account_info(account_info_iter)?;
+        let owner = next_account_info(account_info_iter)?;
+        let metadata_info = next_account_info(account_info_iter)?;
+        let receiver = next_account_info(account_info_iter)?;
+        let signers = account_info_iter.as_slice();
+
+        let mut record = load_record(program_id, pda)?;
+
+        // Ensure the owner on record is correct.
+        if record.owner != *owner.key {
+            return Err(VaultError::WrongOwner.into());
+        }
+
+        let config = load_config(program_id, config_info)?;
+        validate_intermediary(&config, &record.dart)?;
+        authorize(program_id, &mut record, dart, owner, signers)?;
+
+        load_metadata(program_id, metadata_info, pda.key)?;
+
+        save_record(pda, &record)?;
+        close_companion_account(metadata_info, receiver)?;
+        VaultEvent::MetadataChanged {
+            vault: *pda.key,
+            cleared: true,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
+    }
+
+    // Migrate a vault record written by an older version to the current layout.
+    fn migrate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pda = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+
+        if pda.owner != program_id {
+            msg!("invalid program id");
+            return Err(VaultError::InvalidAccountOwner.into());
+        }
+
+        if *system_program_info.key != system_program::id() {
+            msg!("invalid system program id");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut record = VaultRecord::unpack(&pda.data.borrow())?;
+        if record.is_closed() {
+            msg!("Vault record account closed");
+            return Err(VaultError::VaultClosed.into());
+        }
+        if record.version == 0 {
+            msg!("vault record not initialized");
+            return Err(VaultError::NotInitialized.into());
+        }
+        if !record.needs_migration() {
+            msg!("Vault record already at version {}", record.version);
+            return Ok(());
+        }
+
+        if !payer.is_signer {
+            msg!("Missing required payer signature in migrate");
+            return Err(VaultError::MissingSignature.into());
+        }
+
+        if pda.data_len() < VaultRecord::LEN {
+            fund_rent_exemption(payer, pda, system_program_info, VaultRecord::LEN)?;
+            pda.realloc(VaultRecord::LEN, true)?;
+        }
+
+        msg!(
+            "Migrating vault record from version {} to {}",
+            record.version,
+            VaultRecord::CURRENT_VERSION
+        );
+        let previous_version = record.version;
+        record.version = VaultRecord::CURRENT_VERSION;
+        save_record(pda, &record)?;
+        VaultEvent::Migrated {
+            vault: *pda.key,
+            previous_version,
+            version: record.version,
+        }
+        .emit();
+        Ok(())
     }
 }
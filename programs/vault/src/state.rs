@@ -1,8 +1,245 @@
 use {
-    borsh::{BorshDeserialize, BorshSchema, BorshSerialize},
-    solana_program::{program_pack::IsInitialized, pubkey::Pubkey},
+    crate::id,
+    borsh::{
+        schema::{Declaration, Definition},
+        BorshDeserialize, BorshSchema, BorshSerialize,
+    },
+    solana_program::{
+        account_info::AccountInfo,
+        borsh0_10::try_from_slice_unchecked,
+        clock::{Clock, Slot, UnixTimestamp},
+        hash::hashv,
+        program_error::ProgramError,
+        program_pack::IsInitialized,
+        pubkey::Pubkey,
+    },
+    std::collections::HashMap,
 };
 
+/// Seed prefix for vault program derived addresses
+pub const VAULT_SEED_PREFIX: &[u8] = b"vault";
+
+/// Hash a DART key and a caller supplied seed into a vault address seed.
+///
+/// Vault addresses are derived from `[b"vault", hash(dart, seed)]` so the
+/// signer seeds stored in a record never depend on the current DART.
+pub fn vault_seed(dart: &Pubkey, seed: &str) -> [u8; 32] {
+    hashv(&[dart.as_ref(), seed.as_bytes()]).to_bytes()
+}
+
+/// Find the vault address and bump seed for a DART and caller supplied seed.
+pub fn find_vault_address(dart: &Pubkey, seed: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED_PREFIX, &vault_seed(dart, seed)], &id())
+}
+
+/// Seed for the program config address
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Find the program config address and bump seed.
+pub fn find_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], &id())
+}
+
+/// Seed prefix for delegate allowance addresses
+pub const ALLOWANCE_SEED_PREFIX: &[u8] = b"allowance";
+
+/// Find the allowance address and bump seed for a vault and delegate.
+pub fn find_allowance_address(vault: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ALLOWANCE_SEED_PREFIX, vault.as_ref(), delegate.as_ref()],
+        &id(),
+    )
+}
+
+/// Seed prefix for allowlisted withdrawal destination addresses
+pub const DESTINATION_SEED_PREFIX: &[u8] = b"destination";
+
+/// Find the allowlist entry address and bump seed for a vault and destination.
+pub fn find_destination_address(vault: &Pubkey, destination: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            DESTINATION_SEED_PREFIX,
+            vault.as_ref(),
+            destination.as_ref(),
+        ],
+        &id(),
+    )
+}
+
+/// Seed prefix for vault stake account addresses
+pub const STAKE_SEED_PREFIX: &[u8] = b"stake";
+
+/// Find the stake account address and bump seed for a vault and index.
+pub fn find_stake_address(vault: &Pubkey, index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[STAKE_SEED_PREFIX, vault.as_ref(), &index.to_le_bytes()],
+        &id(),
+    )
+}
+
+/// Seed prefix for vault co-owner list addresses
+pub const CO_OWNERS_SEED_PREFIX: &[u8] = b"co_owners";
+
+/// Find the co-owner list address and bump seed for a vault.
+pub fn find_co_owners_address(vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CO_OWNERS_SEED_PREFIX, vault.as_ref()], &id())
+}
+
+/// Seed prefix for vault metadata addresses
+pub const METADATA_SEED_PREFIX: &[u8] = b"metadata";
+
+/// Find the metadata address and bump seed for a vault.
+pub fn find_metadata_address(vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED_PREFIX, vault.as_ref()], &id())
+}
+
+/// Signatures required by state-changing vault instructions.
+#[derive(
+    Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq,
+)]
+pub enum AuthorityMode {
+    /// The DART signs alone (custodial)
+    #[default]
+    Custodial,
+    /// The owner signs alone (self-custody)
+    SelfCustody,
+    /// The DART and owner co-sign
+    CoSign,
+}
+
+impl AuthorityMode {
+    /// Whether the DART must sign.
+    pub fn dart_signs(&self) -> bool {
+        matches!(self, Self::Custodial | Self::CoSign)
+    }
+
+    /// Whether the owner must sign.
+    pub fn owner_signs(&self) -> bool {
+        matches!(self, Self::SelfCustody | Self::CoSign)
+    }
+}
+
+/// Point in time before which funds cannot leave a time-locked vault.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub enum UnlockAt {
+    /// Unix timestamp (seconds), per the `Clock` sysvar
+    Timestamp(UnixTimestamp),
+    /// Slot, per the `Clock` sysvar
+    Slot(Slot),
+}
+
+impl UnlockAt {
+    /// Whether the lock has expired at the given clock.
+    pub fn has_passed(&self, clock: &Clock) -> bool {
+        match *self {
+            Self::Timestamp(timestamp) => clock.unix_timestamp >= timestamp,
+            Self::Slot(slot) => clock.slot >= slot,
+        }
+    }
+
+    /// Whether this lock ends no earlier than another, measured the same way.
+    pub fn extends(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (Self::Timestamp(new), Self::Timestamp(old)) => new >= old,
+            (Self::Slot(new), Self::Slot(old)) => new >= old,
+            _ => false,
+        }
+    }
+}
+
+// Implemented by hand, as the derive emits a never-read struct per tuple variant.
+impl BorshSchema for UnlockAt {
+    fn add_definitions_recursively(definitions: &mut HashMap<Declaration, Definition>) {
+        let variants = vec![
+            ("Timestamp".to_string(), UnixTimestamp::declaration()),
+            ("Slot".to_string(), Slot::declaration()),
+        ];
+        Self::add_definition(
+            Self::declaration(),
+            Definition::Enum { variants },
+            definitions,
+        );
+        UnixTimestamp::add_definitions_recursively(definitions);
+        Slot::add_definitions_recursively(definitions);
+    }
+
+    fn declaration() -> Declaration {
+        "UnlockAt".to_string()
+    }
+}
+
+/// Cap on the lamports leaving a vault per time window.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct WithdrawalLimit {
+    /// Lamports that may be withdrawn per window
+    pub amount: u64,
+    /// Length of the window in seconds
+    pub window: UnixTimestamp,
+}
+
+impl WithdrawalLimit {
+    /// Seconds before a raised withdrawal limit takes effect
+    pub const RAISE_DELAY: UnixTimestamp = 24 * 60 * 60;
+
+    /// Whether a limit is at least as strict as another, allowing no more
+    /// lamports over no shorter a window. No limit is the least strict.
+    pub fn is_within(limit: &Option<Self>, other: &Option<Self>) -> bool {
+        match (limit, other) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(limit), Some(other)) => {
+                limit.amount <= other.amount && limit.window >= other.window
+            }
+        }
+    }
+}
+
+/// Raised withdrawal limit awaiting its delay.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct PendingLimit {
+    /// The new limit, or none to lift the limit
+    pub limit: Option<WithdrawalLimit>,
+    /// Time at which the new limit takes effect
+    pub effective_at: UnixTimestamp,
+}
+
+/// Owner recovery started by the guardians of a vault, which the current
+/// owner can cancel until it completes.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct Recovery {
+    /// The owner to recover the vault to
+    pub new_owner: Pubkey,
+    /// Time at which the recovery can complete
+    pub completes_at: UnixTimestamp,
+}
+
+impl Recovery {
+    /// Seconds the current owner has to cancel a recovery
+    pub const CHALLENGE_PERIOD: UnixTimestamp = 7 * 24 * 60 * 60;
+
+    /// Whether the challenge period is over.
+    pub fn can_complete(&self, clock: &Clock) -> bool {
+        clock.unix_timestamp >= self.completes_at
+    }
+}
+
+/// Beneficiary able to take ownership of a vault once its owner has been
+/// inactive for the inactivity period.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct Inheritance {
+    /// The beneficiary key, or a `Multisig` for a threshold of beneficiaries
+    pub beneficiary: Pubkey,
+    /// Seconds without owner activity after which the beneficiary can claim
+    pub inactivity_period: UnixTimestamp,
+}
+
+impl Inheritance {
+    /// Whether the owner has been inactive long enough since its last activity.
+    pub fn can_claim(&self, last_activity: UnixTimestamp, clock: &Clock) -> bool {
+        clock.unix_timestamp >= last_activity.saturating_add(self.inactivity_period)
+    }
+}
+
 /// Struct providing metadata (and could be extended to support data).
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
 pub struct VaultRecord {
@@ -14,13 +251,136 @@ pub struct VaultRecord {
 
     /// The securities intermediary
     pub dart: Pubkey,
+
+    /// Bump seed of the vault address (zero for vaults not created as a PDA)
+    pub bump: u8,
+
+    /// Hashed address seed of the vault (see `vault_seed`)
+    pub seed: [u8; 32],
+
+    /// Proposed new owner, pending acceptance
+    pub pending_owner: Option<Pubkey>,
+
+    /// Signatures required by state-changing instructions
+    pub authority_mode: AuthorityMode,
+
+    /// Compliance hold blocking ownership changes and withdrawals
+    pub frozen: bool,
+
+    /// Time lock before which funds cannot leave the vault
+    pub unlock_at: Option<UnlockAt>,
+
+    /// Cap on the lamports leaving the vault per time window
+    pub limit: Option<WithdrawalLimit>,
+
+    /// Start of the current withdrawal window
+    pub window_start: UnixTimestamp,
+
+    /// Lamports withdrawn in the current window
+    pub withdrawn: u64,
+
+    /// Raised withdrawal limit awaiting its delay
+    pub pending_limit: Option<PendingLimit>,
+
+    /// Nonce of the last owner approval used, for replay protection
+    pub nonce: u64,
+
+    /// Guardian able to start an owner recovery; a `Multisig` for a threshold
+    /// of guardians
+    pub guardian: Option<Pubkey>,
+
+    /// Owner recovery awaiting its challenge period
+    pub recovery: Option<Recovery>,
+
+    /// Beneficiary able to claim the vault after a period of owner inactivity
+    pub inheritance: Option<Inheritance>,
+
+    /// Time of the last instruction signed by the owner
+    pub last_activity: UnixTimestamp,
+
+    /// Token and stake accounts opened through the vault and not closed yet;
+    /// the vault cannot be closed while any remain
+    pub open_accounts: u32,
 }
 
 impl VaultRecord {
     /// Version to fill in on new created accounts
-    pub const CURRENT_VERSION: u8 = 1;
+    pub const CURRENT_VERSION: u8 = 9;
+    /// Version of the original layout, holding only the owner and DART
+    pub const V1_VERSION: u8 = 1;
+    /// Tombstone version left behind in the data of a closed vault
+    pub const CLOSED_VERSION: u8 = u8::MAX;
     /// Packed vault record space
-    pub const LEN: usize = 65; // 1 + 32 + 32
+    pub const LEN: usize = 337; // 1 + 32 + 32 + 1 + 32 + 33 + 1 + 1 + 10 + 17 + 8 + 8 + 26 + 8 + 33 + 41 + 41 + 8 + 4
+    /// Packed space of a version 1 record
+    pub const V1_LEN: usize = 65; // 1 + 32 + 32
+
+    /// Deserialize a record from account data, ignoring unused trailing space.
+    ///
+    /// Fields introduced by later versions are appended to the layout and
+    /// decode to their defaults from zero bytes, so data written with an older,
+    /// shorter layout is read by zero-padding it to the current length.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() >= Self::LEN {
+            return try_from_slice_unchecked(data).map_err(|e| e.into());
+        }
+        let mut padded = [0; Self::LEN];
+        padded[..data.len()].copy_from_slice(data);
+        try_from_slice_unchecked(&padded).map_err(|e| e.into())
+    }
+
+    /// Whether the record was written by an older version and must be migrated.
+    pub fn needs_migration(&self) -> bool {
+        self.version != 0 && self.version < Self::CURRENT_VERSION
+    }
+
+    /// Whether the record is the tombstone of a closed vault.
+    pub fn is_closed(&self) -> bool {
+        self.version == Self::CLOSED_VERSION
+    }
+
+    /// Apply a pending withdrawal limit once its delay has passed.
+    pub fn settle_limit(&mut self, clock: &Clock) {
+        if let Some(pending) = self.pending_limit {
+            if clock.unix_timestamp >= pending.effective_at {
+                self.limit = pending.limit;
+                self.pending_limit = None;
+            }
+        }
+    }
+
+    /// Count a withdrawal against the current window, starting a new window
+    /// once the previous one has elapsed. Returns false, leaving the record
+    /// unchanged, if the withdrawal would exceed the limit.
+    pub fn record_withdrawal(&mut self, amount: u64, clock: &Clock) -> bool {
+        self.settle_limit(clock);
+        let Some(limit) = self.limit else {
+            return true;
+        };
+        let (window_start, withdrawn) =
+            if clock.unix_timestamp >= self.window_start.saturating_add(limit.window) {
+                (clock.unix_timestamp, 0)
+            } else {
+                (self.window_start, self.withdrawn)
+            };
+        match withdrawn.checked_add(amount) {
+            Some(withdrawn) if withdrawn <= limit.amount => {
+                self.window_start = window_start;
+                self.withdrawn = withdrawn;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Signer seeds for the vault address, for use with `invoke_signed`.
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            VAULT_SEED_PREFIX,
+            &self.seed,
+            std::slice::from_ref(&self.bump),
+        ]
+    }
 }
 
 impl IsInitialized for VaultRecord {
@@ -30,22 +390,465 @@ impl IsInitialized for VaultRecord {
     }
 }
 
+/// Custodian context attached to a vault.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct VaultMetadata {
+    /// External customer reference
+    pub reference: String,
+    /// Display label
+    pub label: String,
+    /// Category the vault is filed under
+    pub category: String,
+}
+
+impl VaultMetadata {
+    /// Cap on the packed metadata
+    pub const MAX_LEN: usize = 256;
+
+    /// Packed space of the metadata.
+    pub fn packed_len(&self) -> usize {
+        4 + self.reference.len() + 4 + self.label.len() + 4 + self.category.len()
+    }
+}
+
+/// Metadata of a vault, stored in a PDA derived from the vault.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct Metadata {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Bump seed of the metadata address
+    pub bump: u8,
+
+    /// The vault the metadata is attached to
+    pub vault: Pubkey,
+
+    /// The custodian context
+    pub metadata: VaultMetadata,
+}
+
+impl Metadata {
+    /// Version to fill in on new created metadata accounts
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Packed metadata account space
+    pub const LEN: usize = 290; // 1 + 1 + 32 + 256
+
+    /// Deserialize a metadata account from account data, ignoring unused trailing space.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Seeds used by the program to sign for the metadata address.
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            METADATA_SEED_PREFIX,
+            self.vault.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+impl IsInitialized for Metadata {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
+/// Fee schedule applied to lamports paid out of a vault.
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct FeeSchedule {
+    /// Fee rate in basis points of the amount paid out
+    pub basis_points: u16,
+
+    /// Flat minimum fee in lamports (zero for no minimum)
+    pub minimum: u64,
+}
+
+impl FeeSchedule {
+    /// Basis points in a whole
+    pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+    /// Calculate the fee for a payout, capped at the payout amount.
+    /// Returns `None` on overflow.
+    pub fn fee_for(&self, amount: u64) -> Option<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.basis_points as u128)?
+            .checked_div(Self::MAX_BASIS_POINTS as u128)?;
+        let fee = u64::try_from(fee).ok()?.max(self.minimum);
+        Some(fee.min(amount))
+    }
+}
+
+/// Global program configuration, stored in a singleton PDA.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct ProgramConfig {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Bump seed of the config address
+    pub bump: u8,
+
+    /// The authority allowed to update the config
+    pub admin: Pubkey,
+
+    /// The fee schedule applied on close and withdrawal
+    pub fees: FeeSchedule,
+
+    /// The account receiving fees
+    pub fee_collector: Pubkey,
+
+    /// Global pause blocking ownership changes and withdrawals on every vault
+    pub paused: bool,
+
+    /// The securities intermediaries (DARTs) recognized by the program
+    pub intermediaries: Vec<Pubkey>,
+}
+
+impl ProgramConfig {
+    /// Version to fill in on new created configs
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Maximum number of recognized intermediaries
+    pub const MAX_INTERMEDIARIES: usize = 16;
+    /// Packed program config space
+    pub const LEN: usize = 593; // 1 + 1 + 32 + (2 + 8) + 32 + 1 + 4 + 32 * 16
+
+    /// Deserialize a config from account data, ignoring unused trailing space.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Whether the given key is a recognized intermediary.
+    pub fn is_intermediary(&self, key: &Pubkey) -> bool {
+        self.intermediaries.contains(key)
+    }
+}
+
+impl IsInitialized for ProgramConfig {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
+/// M-of-N multisig authority, able to stand in for a DART wherever its
+/// signature is required.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct Multisig {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Number of signers required
+    pub threshold: u8,
+
+    /// The signers able to approve on behalf of the multisig
+    pub signers: Vec<Pubkey>,
+}
+
+impl Multisig {
+    /// Version to fill in on new created multisigs
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Maximum number of signers
+    pub const MAX_SIGNERS: usize = 11;
+    /// Packed multisig space
+    pub const LEN: usize = 358; // 1 + 1 + 4 + 32 * 11
+
+    /// Deserialize a multisig from account data, ignoring unused trailing space.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Count the distinct multisig signers that signed among the given accounts.
+    pub fn approvals(&self, accounts: &[AccountInfo]) -> usize {
+        self.signers
+            .iter()
+            .filter(|signer| {
+                accounts
+                    .iter()
+                    .any(|account| account.is_signer && account.key == *signer)
+            })
+            .count()
+    }
+}
+
+impl IsInitialized for Multisig {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
+/// Spending allowance letting a delegate withdraw from a vault, stored in a
+/// PDA derived from the vault and delegate.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct Allowance {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Bump seed of the allowance address
+    pub bump: u8,
+
+    /// The vault the allowance draws from
+    pub vault: Pubkey,
+
+    /// The delegate allowed to withdraw
+    pub delegate: Pubkey,
+
+    /// Lamports the delegate may still withdraw
+    pub amount: u64,
+
+    /// Time after which the allowance can no longer be used, if any
+    pub expires_at: Option<UnixTimestamp>,
+
+    /// The vault owner who granted the allowance; it lapses once the vault
+    /// changes hands
+    pub owner: Pubkey,
+
+    /// The only account the delegate may withdraw to
+    pub recipient: Pubkey,
+}
+
+impl Allowance {
+    /// Version to fill in on new created allowances
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Packed allowance space
+    pub const LEN: usize = 147; // 1 + 1 + 32 + 32 + 8 + 9 + 32 + 32
+
+    /// Deserialize an allowance from account data, ignoring unused trailing space.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Whether the allowance has expired at the given clock.
+    pub fn has_expired(&self, clock: &Clock) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| clock.unix_timestamp >= expires_at)
+    }
+
+    /// Seeds used by the program to sign for the allowance address.
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            ALLOWANCE_SEED_PREFIX,
+            self.vault.as_ref(),
+            self.delegate.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+impl IsInitialized for Allowance {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
+/// Allowlisted withdrawal destination of a vault, stored in a PDA derived
+/// from the vault and destination.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct Destination {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Bump seed of the allowlist entry address
+    pub bump: u8,
+
+    /// The vault the destination is allowlisted for
+    pub vault: Pubkey,
+
+    /// The address funds may be withdrawn to
+    pub destination: Pubkey,
+
+    /// Time at which the destination may first be withdrawn to
+    pub active_at: UnixTimestamp,
+
+    /// The vault owner who added the entry; it lapses once the vault changes
+    /// hands
+    pub owner: Pubkey,
+}
+
+impl Destination {
+    /// Version to fill in on new created allowlist entries
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Packed allowlist entry space
+    pub const LEN: usize = 106; // 1 + 1 + 32 + 32 + 8 + 32
+    /// Seconds before a newly allowlisted destination may be withdrawn to
+    pub const COOLING_OFF: UnixTimestamp = 48 * 60 * 60;
+
+    /// Deserialize an allowlist entry from account data.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Whether the cooling-off period has passed at the given clock.
+    pub fn is_active(&self, clock: &Clock) -> bool {
+        clock.unix_timestamp >= self.active_at
+    }
+
+    /// Seeds used by the program to sign for the allowlist entry address.
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            DESTINATION_SEED_PREFIX,
+            self.vault.as_ref(),
+            self.destination.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+impl IsInitialized for Destination {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
+/// Co-owner of a joint vault, with the weight of their share.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq, Eq)]
+pub struct CoOwner {
+    /// The co-owner
+    pub owner: Pubkey,
+
+    /// Weight of the co-owner's share of the vault
+    pub shares: u32,
+}
+
+/// Co-owners of a joint vault, stored in a PDA derived from the vault.
+///
+/// The vault record names this account as its owner. Like a `Multisig`, it
+/// stands in for the owner signature once enough co-owners sign, and payouts
+/// to the owner are split between the co-owners pro rata to their shares.
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, BorshSchema, PartialEq)]
+pub struct CoOwners {
+    /// Struct version, allows for upgrades to the program
+    pub version: u8,
+
+    /// Bump seed of the co-owner list address
+    pub bump: u8,
+
+    /// The co-owned vault
+    pub vault: Pubkey,
+
+    /// Number of co-owners required to approve in place of the owner
+    pub threshold: u8,
+
+    /// The co-owners and their shares
+    pub owners: Vec<CoOwner>,
+}
+
+impl CoOwners {
+    /// Version to fill in on new created co-owner lists
+    pub const CURRENT_VERSION: u8 = 1;
+    /// Maximum number of co-owners
+    pub const MAX_OWNERS: usize = 8;
+    /// Packed co-owner list space
+    pub const LEN: usize = 327; // 1 + 1 + 32 + 1 + 4 + (32 + 4) * 8
+
+    /// Deserialize a co-owner list from account data, ignoring unused trailing space.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        try_from_slice_unchecked(data).map_err(|e| e.into())
+    }
+
+    /// Position of a co-owner in the list, if listed.
+    pub fn position(&self, owner: &Pubkey) -> Option<usize> {
+        self.owners
+            .iter()
+            .position(|co_owner| co_owner.owner == *owner)
+    }
+
+    /// Count the distinct co-owners that signed among the given accounts.
+    pub fn approvals(&self, accounts: &[AccountInfo]) -> usize {
+        self.owners
+            .iter()
+            .filter(|co_owner| {
+                accounts
+                    .iter()
+                    .any(|account| account.is_signer && *account.key == co_owner.owner)
+            })
+            .count()
+    }
+
+    /// Split an amount between the co-owners pro rata to their shares, in
+    /// list order. The rounding remainder goes to the first co-owner.
+    /// Returns `None` if no shares are held.
+    pub fn split(&self, amount: u64) -> Option<Vec<u64>> {
+        let total: u128 = self
+            .owners
+            .iter()
+            .map(|co_owner| co_owner.shares as u128)
+            .sum();
+        if total == 0 {
+            return None;
+        }
+        let mut parts: Vec<u64> = self
+            .owners
+            .iter()
+            .map(|co_owner| (amount as u128 * co_owner.shares as u128 / total) as u64)
+            .collect();
+        let remainder = amount - parts.iter().sum::<u64>();
+        parts[0] += remainder;
+        Some(parts)
+    }
+
+    /// Seeds used by the program to sign for the co-owner list address.
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            CO_OWNERS_SEED_PREFIX,
+            self.vault.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+impl IsInitialized for CoOwners {
+    /// Is initialized
+    fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use solana_program::program_error::ProgramError;
+    use solana_program::{borsh0_10::get_packed_len, program_error::ProgramError};
 
     /// Version for tests
-    pub const TEST_VERSION: u8 = 1;
+    pub const TEST_VERSION: u8 = 9;
     /// Owner pubkey
     pub const OWNER_PUBKEY: Pubkey = Pubkey::new_from_array([99; 32]);
     /// DART pubkey
     pub const DART_PUBKEY: Pubkey = Pubkey::new_from_array([66; 32]);
+    /// Bump seed for tests
+    pub const TEST_BUMP: u8 = 254;
+    /// Address seed for tests
+    pub const TEST_SEED: [u8; 32] = [7; 32];
     /// VaultRecord for tests
     pub const TEST_RECORD_DATA: VaultRecord = VaultRecord {
         version: TEST_VERSION,
         owner: OWNER_PUBKEY,
         dart: DART_PUBKEY,
+        bump: TEST_BUMP,
+        seed: TEST_SEED,
+        pending_owner: None,
+        authority_mode: AuthorityMode::CoSign,
+        frozen: false,
+        unlock_at: None,
+        limit: None,
+        window_start: 0,
+        withdrawn: 0,
+        pending_limit: None,
+        nonce: 0,
+        guardian: None,
+        recovery: None,
+        inheritance: None,
+        last_activity: 0,
+        open_accounts: 0,
+    };
+
+    /// Withdrawal limit for tests
+    pub const TEST_LIMIT: WithdrawalLimit = WithdrawalLimit {
+        amount: 1_000,
+        window: 60,
     };
 
     #[test]
@@ -53,6 +856,21 @@ pub mod tests {
         let mut expected = vec![TEST_VERSION];
         expected.extend_from_slice(&OWNER_PUBKEY.to_bytes());
         expected.extend_from_slice(&DART_PUBKEY.to_bytes());
+        expected.push(TEST_BUMP);
+        expected.extend_from_slice(&TEST_SEED);
+        expected.push(0);
+        expected.push(2);
+        expected.push(0);
+        expected.push(0);
+        expected.push(0);
+        expected.extend_from_slice(&[0; 16]);
+        expected.push(0);
+        expected.extend_from_slice(&[0; 8]);
+        expected.push(0);
+        expected.push(0);
+        expected.push(0);
+        expected.extend_from_slice(&[0; 8]);
+        expected.extend_from_slice(&[0; 4]);
         assert_eq!(TEST_RECORD_DATA.try_to_vec().unwrap(), expected);
         assert_eq!(
             VaultRecord::try_from_slice(&expected).unwrap(),
@@ -60,6 +878,40 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn unpack_padded_data() {
+        let record = VaultRecord {
+            pending_owner: Some(OWNER_PUBKEY),
+            unlock_at: Some(UnlockAt::Slot(1)),
+            limit: Some(TEST_LIMIT),
+            pending_limit: Some(PendingLimit {
+                limit: Some(TEST_LIMIT),
+                effective_at: 1,
+            }),
+            guardian: Some(DART_PUBKEY),
+            recovery: Some(Recovery {
+                new_owner: OWNER_PUBKEY,
+                completes_at: 1,
+            }),
+            inheritance: Some(Inheritance {
+                beneficiary: DART_PUBKEY,
+                inactivity_period: 1,
+            }),
+            last_activity: 1,
+            open_accounts: 1,
+            ..TEST_RECORD_DATA
+        };
+        let mut data = record.try_to_vec().unwrap();
+        assert_eq!(data.len(), VaultRecord::LEN);
+        assert_eq!(get_packed_len::<VaultRecord>(), VaultRecord::LEN);
+        assert_eq!(VaultRecord::unpack(&data).unwrap(), record);
+
+        // Unused space is ignored when the pending owner is cleared.
+        data.fill(0);
+        borsh::to_writer(&mut data[..], &TEST_RECORD_DATA).unwrap();
+        assert_eq!(VaultRecord::unpack(&data).unwrap(), TEST_RECORD_DATA);
+    }
+
     #[test]
     fn deserialize_invalid_slice() {
         let mut expected = vec![TEST_VERSION];
@@ -67,4 +919,350 @@ pub mod tests {
         let err: ProgramError = VaultRecord::try_from_slice(&expected).unwrap_err().into();
         assert!(matches!(err, ProgramError::BorshIoError(_)));
     }
+
+    #[test]
+    fn config_len_fits_max_intermediaries() {
+        let config = ProgramConfig {
+            version: ProgramConfig::CURRENT_VERSION,
+            bump: 255,
+            admin: OWNER_PUBKEY,
+            fees: FeeSchedule::default(),
+            fee_collector: DART_PUBKEY,
+            paused: false,
+            intermediaries: vec![DART_PUBKEY; ProgramConfig::MAX_INTERMEDIARIES],
+        };
+        assert_eq!(config.try_to_vec().unwrap().len(), ProgramConfig::LEN);
+        assert!(config.is_intermediary(&DART_PUBKEY));
+        assert!(!config.is_intermediary(&OWNER_PUBKEY));
+    }
+
+    #[test]
+    fn multisig_len_fits_max_signers() {
+        let multisig = Multisig {
+            version: Multisig::CURRENT_VERSION,
+            threshold: 2,
+            signers: vec![DART_PUBKEY; Multisig::MAX_SIGNERS],
+        };
+        assert_eq!(multisig.try_to_vec().unwrap().len(), Multisig::LEN);
+    }
+
+    #[test]
+    fn allowance_expiry() {
+        let (vault, _) = find_vault_address(&DART_PUBKEY, "seed");
+        let (address, bump) = find_allowance_address(&vault, &OWNER_PUBKEY);
+        let allowance = Allowance {
+            version: Allowance::CURRENT_VERSION,
+            bump,
+            vault,
+            delegate: OWNER_PUBKEY,
+            amount: 1_000,
+            expires_at: Some(1_700_000_000),
+            owner: DART_PUBKEY,
+            recipient: OWNER_PUBKEY,
+        };
+        assert_eq!(allowance.try_to_vec().unwrap().len(), Allowance::LEN);
+        assert_eq!(
+            Pubkey::create_program_address(&allowance.signer_seeds(), &id()).unwrap(),
+            address
+        );
+
+        let clock = Clock {
+            unix_timestamp: 1_699_999_999,
+            ..Clock::default()
+        };
+        assert!(!allowance.has_expired(&clock));
+        let clock = Clock {
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        };
+        assert!(allowance.has_expired(&clock));
+        let open_ended = Allowance {
+            expires_at: None,
+            ..allowance
+        };
+        assert!(!open_ended.has_expired(&clock));
+    }
+
+    #[test]
+    fn destination_cooling_off() {
+        let (vault, _) = find_vault_address(&DART_PUBKEY, "seed");
+        let (address, bump) = find_destination_address(&vault, &OWNER_PUBKEY);
+        let destination = Destination {
+            version: Destination::CURRENT_VERSION,
+            bump,
+            vault,
+            destination: OWNER_PUBKEY,
+            active_at: 1_700_000_000,
+            owner: DART_PUBKEY,
+        };
+        assert_eq!(destination.try_to_vec().unwrap().len(), Destination::LEN);
+        assert_eq!(
+            Pubkey::create_program_address(&destination.signer_seeds(), &id()).unwrap(),
+            address
+        );
+        assert_ne!(address, find_allowance_address(&vault, &OWNER_PUBKEY).0);
+
+        let mut clock = Clock {
+            unix_timestamp: 1_699_999_999,
+            ..Clock::default()
+        };
+        assert!(!destination.is_active(&clock));
+        clock.unix_timestamp = 1_700_000_000;
+        assert!(destination.is_active(&clock));
+    }
+
+    #[test]
+    fn co_owners_split_pro_rata() {
+        let (vault, _) = find_vault_address(&DART_PUBKEY, "seed");
+        let (address, bump) = find_co_owners_address(&vault);
+        let co_owners = CoOwners {
+            version: CoOwners::CURRENT_VERSION,
+            bump,
+            vault,
+            threshold: 2,
+            owners: vec![
+                CoOwner {
+                    owner: OWNER_PUBKEY,
+                    shares: 1,
+                },
+                CoOwner {
+                    owner: DART_PUBKEY,
+                    shares: 2,
+                },
+            ],
+        };
+        assert_eq!(
+            Pubkey::create_program_address(&co_owners.signer_seeds(), &id()).unwrap(),
+            address
+        );
+        assert_eq!(co_owners.position(&DART_PUBKEY), Some(1));
+        assert_eq!(co_owners.position(&address), None);
+
+        assert_eq!(co_owners.split(3_000), Some(vec![1_000, 2_000]));
+        assert_eq!(co_owners.split(1_000), Some(vec![334, 666]));
+        let parts = co_owners.split(u64::MAX).unwrap();
+        assert_eq!(
+            parts.iter().map(|part| *part as u128).sum::<u128>(),
+            u64::MAX as u128
+        );
+
+        let max = CoOwners {
+            owners: vec![co_owners.owners[0]; CoOwners::MAX_OWNERS],
+            ..co_owners
+        };
+        assert_eq!(max.try_to_vec().unwrap().len(), CoOwners::LEN);
+        let no_shares = CoOwners {
+            owners: vec![CoOwner {
+                owner: OWNER_PUBKEY,
+                shares: 0,
+            }],
+            ..max
+        };
+        assert_eq!(no_shares.split(1_000), None);
+    }
+
+    #[test]
+    fn fee_for_payout() {
+        let fees = FeeSchedule {
+            basis_points: 25,
+            minimum: 5_000,
+        };
+        assert_eq!(fees.fee_for(10_000_000), Some(25_000));
+        assert_eq!(fees.fee_for(1_000_000), Some(5_000));
+        assert_eq!(fees.fee_for(1_000), Some(1_000));
+        assert_eq!(FeeSchedule::default().fee_for(u64::MAX), Some(0));
+
+        let all = FeeSchedule {
+            basis_points: FeeSchedule::MAX_BASIS_POINTS,
+            minimum: 0,
+        };
+        assert_eq!(all.fee_for(u64::MAX), Some(u64::MAX));
+    }
+
+    #[test]
+    fn unpack_v1_data() {
+        let mut data = vec![VaultRecord::V1_VERSION];
+        data.extend_from_slice(&OWNER_PUBKEY.to_bytes());
+        data.extend_from_slice(&DART_PUBKEY.to_bytes());
+        assert_eq!(data.len(), VaultRecord::V1_LEN);
+
+        let record = VaultRecord::unpack(&data).unwrap();
+        assert_eq!(
+            record,
+            VaultRecord {
+                version: VaultRecord::V1_VERSION,
+                owner: OWNER_PUBKEY,
+                dart: DART_PUBKEY,
+                bump: 0,
+                seed: [0; 32],
+                pending_owner: None,
+                authority_mode: AuthorityMode::Custodial,
+                frozen: false,
+                unlock_at: None,
+                limit: None,
+                window_start: 0,
+                withdrawn: 0,
+                pending_limit: None,
+                nonce: 0,
+                guardian: None,
+                recovery: None,
+                inheritance: None,
+                last_activity: 0,
+                open_accounts: 0,
+            }
+        );
+        assert!(record.needs_migration());
+        assert!(!record.is_initialized());
+        assert!(!TEST_RECORD_DATA.needs_migration());
+    }
+
+    #[test]
+    fn metadata_fits_account() {
+        let (vault, _) = find_vault_address(&DART_PUBKEY, "seed");
+        let (address, bump) = find_metadata_address(&vault);
+        let mut metadata = Metadata {
+            version: Metadata::CURRENT_VERSION,
+            bump,
+            vault,
+            metadata: VaultMetadata {
+                reference: "cust-42".to_string(),
+                label: "Savings".to_string(),
+                category: "retail".to_string(),
+            },
+        };
+        assert_eq!(
+            Pubkey::create_program_address(&metadata.signer_seeds(), &id()).unwrap(),
+            address
+        );
+
+        let mut data = vec![0; Metadata::LEN];
+        borsh::to_writer(&mut data[..], &metadata).unwrap();
+        assert_eq!(Metadata::unpack(&data).unwrap(), metadata);
+
+        metadata.metadata = VaultMetadata {
+            reference: "x".repeat(VaultMetadata::MAX_LEN - 12),
+            ..VaultMetadata::default()
+        };
+        assert_eq!(metadata.metadata.packed_len(), VaultMetadata::MAX_LEN);
+        assert_eq!(metadata.try_to_vec().unwrap().len(), Metadata::LEN);
+    }
+
+    #[test]
+    fn inheritance_claimable_after_inactivity() {
+        let inheritance = Inheritance {
+            beneficiary: DART_PUBKEY,
+            inactivity_period: 100,
+        };
+        let clock = Clock {
+            unix_timestamp: 1_700_000_100,
+            ..Clock::default()
+        };
+        assert!(inheritance.can_claim(1_700_000_000, &clock));
+        assert!(!inheritance.can_claim(1_700_000_001, &clock));
+        let forever = Inheritance {
+            inactivity_period: UnixTimestamp::MAX,
+            ..inheritance
+        };
+        assert!(!forever.can_claim(1_700_000_000, &clock));
+    }
+
+    #[test]
+    fn unlock_at_against_clock() {
+        let clock = Clock {
+            slot: 100,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        };
+        assert!(UnlockAt::Slot(100).has_passed(&clock));
+        assert!(!UnlockAt::Slot(101).has_passed(&clock));
+        assert!(UnlockAt::Timestamp(1_700_000_000).has_passed(&clock));
+        assert!(!UnlockAt::Timestamp(1_700_000_001).has_passed(&clock));
+
+        assert!(UnlockAt::Slot(101).extends(&UnlockAt::Slot(100)));
+        assert!(!UnlockAt::Slot(99).extends(&UnlockAt::Slot(100)));
+        assert!(!UnlockAt::Timestamp(i64::MAX).extends(&UnlockAt::Slot(100)));
+    }
+
+    #[test]
+    fn withdrawals_within_limit_window() {
+        let mut record = VaultRecord {
+            limit: Some(TEST_LIMIT),
+            ..TEST_RECORD_DATA
+        };
+        let mut clock = Clock {
+            unix_timestamp: 1_000,
+            ..Clock::default()
+        };
+        assert!(record.record_withdrawal(600, &clock));
+        assert!(!record.record_withdrawal(600, &clock));
+        assert!(record.record_withdrawal(400, &clock));
+        assert_eq!((record.window_start, record.withdrawn), (1_000, 1_000));
+
+        // A new window starts once the previous one elapses.
+        clock.unix_timestamp = 1_060;
+        assert!(record.record_withdrawal(600, &clock));
+        assert_eq!((record.window_start, record.withdrawn), (1_060, 600));
+
+        // A pending raise applies once its delay has passed.
+        record.pending_limit = Some(PendingLimit {
+            limit: None,
+            effective_at: 1_100,
+        });
+        assert!(!record.record_withdrawal(600, &clock));
+        clock.unix_timestamp = 1_100;
+        assert!(record.record_withdrawal(u64::MAX, &clock));
+        assert_eq!(record.limit, None);
+        assert_eq!(record.pending_limit, None);
+    }
+
+    #[test]
+    fn withdrawal_limit_strictness() {
+        let looser = WithdrawalLimit {
+            amount: 2_000,
+            ..TEST_LIMIT
+        };
+        let shorter = WithdrawalLimit {
+            window: 30,
+            ..TEST_LIMIT
+        };
+        assert!(WithdrawalLimit::is_within(&Some(TEST_LIMIT), &None));
+        assert!(WithdrawalLimit::is_within(&Some(TEST_LIMIT), &Some(looser)));
+        assert!(!WithdrawalLimit::is_within(
+            &Some(looser),
+            &Some(TEST_LIMIT)
+        ));
+        assert!(!WithdrawalLimit::is_within(
+            &Some(shorter),
+            &Some(TEST_LIMIT)
+        ));
+        assert!(!WithdrawalLimit::is_within(&None, &Some(TEST_LIMIT)));
+        assert!(WithdrawalLimit::is_within(&None, &None));
+    }
+
+    #[test]
+    fn tombstone_is_closed_and_uninitialized() {
+        let mut data = [0; VaultRecord::LEN];
+        data[0] = VaultRecord::CLOSED_VERSION;
+        let record = VaultRecord::unpack(&data).unwrap();
+        assert!(record.is_closed());
+        assert!(!record.is_initialized());
+        assert!(!TEST_RECORD_DATA.is_closed());
+    }
+
+    #[test]
+    fn vault_address_is_derived_per_dart() {
+        let other_dart = Pubkey::new_from_array([67; 32]);
+        let (pda, bump) = find_vault_address(&DART_PUBKEY, "seed");
+        assert_ne!(pda, find_vault_address(&other_dart, "seed").0);
+
+        let record = VaultRecord {
+            bump,
+            seed: vault_seed(&DART_PUBKEY, "seed"),
+            ..TEST_RECORD_DATA
+        };
+        assert_eq!(
+            Pubkey::create_program_address(&record.signer_seeds(), &id()).unwrap(),
+            pda
+        );
+    }
 }
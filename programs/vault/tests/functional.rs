@@ -1,21 +1,79 @@
 #![cfg(feature = "test-sbf")]
 use {
+    borsh::BorshSerialize,
     solana_program::{
-        borsh0_10::get_packed_len, instruction::InstructionError, pubkey::Pubkey, rent::Rent,
+        borsh0_10::get_packed_len,
+        clock::{Clock, DEFAULT_MS_PER_SLOT},
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+        stake::state::StakeStateV2,
         system_instruction,
+        vote::{
+            instruction::{self as vote_instruction, CreateVoteAccountConfig},
+            state::{VoteInit, VoteState},
+        },
     },
     solana_program_test::*,
     solana_sdk::{
+        account::Account,
         signature::{Keypair, Signer},
         transaction::{Transaction, TransactionError},
     },
-    vault::{id, instruction, processor::Processor, state::VaultRecord},
+    spl_associated_token_account::{
+        get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+    },
+    spl_token_2022::extension::StateWithExtensions,
+    vault::{
+        approval::{ApprovalAction, OwnerApproval},
+        error::VaultError,
+        id, instruction,
+        processor::Processor,
+        state::{
+            find_allowance_address, find_co_owners_address, find_config_address,
+            find_metadata_address, find_stake_address, find_vault_address, vault_seed, Allowance,
+            AuthorityMode, CoOwner, CoOwners, Destination, FeeSchedule, Inheritance, Metadata,
+            Multisig, ProgramConfig, Recovery, UnlockAt, VaultMetadata, VaultRecord,
+            WithdrawalLimit, VAULT_SEED_PREFIX,
+        },
+    },
 };
 
 fn program_test() -> ProgramTest {
     ProgramTest::new("vault", id(), processor!(Processor::process_instruction))
 }
 
+// Helper: fetch and decode a vault record.
+async fn get_record(context: &mut ProgramTestContext, pda: Pubkey) -> VaultRecord {
+    let account = context
+        .banks_client
+        .get_account(pda)
+        .await
+        .unwrap()
+        .unwrap();
+    VaultRecord::unpack(&account.data).unwrap()
+}
+
+// Helper: initialize the program config, with the payer as admin.
+async fn initialize_config(context: &mut ProgramTestContext, intermediaries: &[Pubkey]) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::initialize_config(
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            intermediaries.to_vec(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
 // Helper: create and initialize a vault account.
 async fn initialize_account(
     context: &mut ProgramTestContext,
@@ -37,7 +95,7 @@ async fn initialize_account(
                 space as u64,
                 &id(),
             ),
-            instruction::initialize(&pda.pubkey(), &dart.pubkey(), &owner.pubkey()),
+            instruction::initialize(&pda.pubkey(), &dart.pubkey(), &owner.pubkey(), None),
         ],
         Some(&context.payer.pubkey()),
         &[&context.payer, pda, dart],
@@ -57,13 +115,10 @@ async fn initialize_success() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     initialize_account(&mut context, &pda, &dart, &owner).await;
-    let account_data = context
-        .banks_client
-        .get_account_data_with_borsh::<VaultRecord>(pda.pubkey())
-        .await
-        .unwrap();
+    let account_data = get_record(&mut context, pda.pubkey()).await;
     assert_eq!(account_data.dart, dart.pubkey());
     assert_eq!(account_data.owner, owner.pubkey());
     assert_eq!(account_data.version, VaultRecord::CURRENT_VERSION);
@@ -77,6 +132,7 @@ async fn initialize_with_seed_success() {
     let seed = "U5f76katXToqua7SJzvP7"; // Could be DART account primary key
     let pda = Pubkey::create_with_seed(&dart.pubkey(), seed, &id()).unwrap();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     // Rent
     let space = get_packed_len::<VaultRecord>();
@@ -94,7 +150,7 @@ async fn initialize_with_seed_success() {
                 space as u64,
                 &id(),
             ),
-            instruction::initialize(&pda, &dart.pubkey(), &owner.pubkey()),
+            instruction::initialize(&pda, &dart.pubkey(), &owner.pubkey(), None),
         ],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
@@ -105,11 +161,7 @@ async fn initialize_with_seed_success() {
         .process_transaction(transaction)
         .await
         .unwrap();
-    let account_data = context
-        .banks_client
-        .get_account_data_with_borsh::<VaultRecord>(pda)
-        .await
-        .unwrap();
+    let account_data = get_record(&mut context, pda).await;
     assert_eq!(account_data.dart, dart.pubkey());
     assert_eq!(account_data.owner, owner.pubkey());
     assert_eq!(account_data.version, VaultRecord::CURRENT_VERSION);
@@ -122,6 +174,7 @@ async fn initialize_twice_fail() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     // First init (success)
     initialize_account(&mut context, &pda, &dart, &owner).await;
@@ -132,6 +185,7 @@ async fn initialize_twice_fail() {
             &pda.pubkey(),
             &dart.pubkey(),
             &owner.pubkey(),
+            None,
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
@@ -144,7 +198,10 @@ async fn initialize_twice_fail() {
             .await
             .unwrap_err()
             .unwrap(),
-        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::AlreadyInitialized as u32)
+        )
     );
 }
 
@@ -155,19 +212,21 @@ async fn transfer_owner_success() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     initialize_account(&mut context, &pda, &dart, &owner).await;
 
     // The new owner
     let new_owner = Keypair::new();
 
-    // Tx must be signed by DART -and- the existing owner.
+    // Custodial vaults only require the DART signature.
     let transaction = Transaction::new_signed_with_payer(
         &[instruction::transfer_owner(
             &pda.pubkey(),
             &dart.pubkey(),
             &owner.pubkey(),
             &new_owner.pubkey(),
+            AuthorityMode::Custodial,
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
@@ -179,11 +238,7 @@ async fn transfer_owner_success() {
         .await
         .unwrap();
 
-    let record = context
-        .banks_client
-        .get_account_data_with_borsh::<VaultRecord>(pda.pubkey())
-        .await
-        .unwrap();
+    let record = get_record(&mut context, pda.pubkey()).await;
 
     // Ensure the new owner was set in the record.
     assert_eq!(record.owner, new_owner.pubkey());
@@ -196,6 +251,7 @@ async fn transfer_owner_fail_wrong_owner() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     initialize_account(&mut context, &pda, &dart, &owner).await;
 
@@ -211,6 +267,7 @@ async fn transfer_owner_fail_wrong_owner() {
             &dart.pubkey(),
             &wrong_owner.pubkey(),
             &new_owner.pubkey(),
+            AuthorityMode::Custodial,
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
@@ -224,7 +281,10 @@ async fn transfer_owner_fail_wrong_owner() {
             .await
             .unwrap_err()
             .unwrap(),
-        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WrongOwner as u32)
+        )
     );
 }
 
@@ -235,15 +295,22 @@ async fn close_account_success() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     initialize_account(&mut context, &pda, &dart, &owner).await;
 
+    let deposit = 5_000_000;
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction::close_account(
-            &pda.pubkey(),
-            &dart.pubkey(),
-            &owner.pubkey(),
-        )],
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &pda.pubkey(), deposit),
+            instruction::close_account(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &context.payer.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+        ],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
         context.last_blockhash,
@@ -260,8 +327,15 @@ async fn close_account_success() {
         .await
         .unwrap()
         .unwrap();
+    assert_eq!(recipient.lamports, deposit);
+
+    // The tombstone keeps its rent so the runtime never deletes it.
     assert_eq!(
-        recipient.lamports,
+        context
+            .banks_client
+            .get_balance(pda.pubkey())
+            .await
+            .unwrap(),
         Rent::default().minimum_balance(get_packed_len::<VaultRecord>())
     );
 }
@@ -273,6 +347,7 @@ async fn close_account_fail_wrong_owner() {
     let pda = Keypair::new();
     let dart = Keypair::new();
     let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
 
     initialize_account(&mut context, &pda, &dart, &owner).await;
 
@@ -282,6 +357,223 @@ async fn close_account_fail_wrong_owner() {
             &pda.pubkey(),
             &dart.pubkey(),
             &wrong_owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WrongOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_leaves_tombstone() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda.pubkey()).await;
+    assert!(record.is_closed());
+    assert_eq!(record.owner, Pubkey::default());
+    assert_eq!(record.dart, Pubkey::default());
+
+    // The tombstone can never be re-initialized
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::initialize(
+            &pda.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::VaultClosed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_then_fail_revive() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::close_account(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &context.payer.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+            instruction::transfer_owner(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &new_owner.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::VaultClosed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_then_fail_recreate() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::close_account(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &context.payer.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+            instruction::create_vault(
+                &context.payer.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                "funded",
+                AuthorityMode::Custodial,
+                None,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::VaultClosed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_then_fail_recreate_later() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        5_000_000,
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // The tombstone outlives the closing transaction, so the seed stays spent.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_vault(
+            &context.payer.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            "funded",
+            AuthorityMode::Custodial,
+            None,
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &dart],
@@ -294,6 +586,4619 @@ async fn close_account_fail_wrong_owner() {
             .await
             .unwrap_err()
             .unwrap(),
-        TransactionError::InstructionError(0, InstructionError::IllegalOwner)
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::VaultClosed as u32)
+        )
+    );
+    assert!(get_record(&mut context, pda).await.is_closed());
+}
+
+#[tokio::test]
+async fn withdraw_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    // Deposit into the vault, then withdraw part of it.
+    let deposit = 5_000_000;
+    let amount = 2_000_000;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &pda.pubkey(), deposit),
+            instruction::withdraw(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &context.payer.pubkey(),
+                amount,
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let recipient = context
+        .banks_client
+        .get_account(owner.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(recipient.lamports, amount);
+
+    let vault = context
+        .banks_client
+        .get_account(pda.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        vault.lamports,
+        Rent::default().minimum_balance(VaultRecord::LEN) + deposit - amount
+    );
+}
+
+#[tokio::test]
+async fn withdraw_fail_insufficient_funds() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    // Only the rent-exempt reserve is in the vault; nothing can be withdrawn.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::withdraw(
+            &pda.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            1,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InsufficientFunds as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn create_vault_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+    let seed = "U5f76katXToqua7SJzvP7";
+    let (pda, bump) = find_vault_address(&dart.pubkey(), seed);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::create_vault(
+            &context.payer.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            seed,
+            AuthorityMode::Custodial,
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, id());
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(VaultRecord::LEN)
+    );
+
+    let record = VaultRecord::unpack(&account.data).unwrap();
+    assert_eq!(record.dart, dart.pubkey());
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.bump, bump);
+    assert_eq!(record.seed, vault_seed(&dart.pubkey(), seed));
+    assert_eq!(record.version, VaultRecord::CURRENT_VERSION);
+}
+
+#[tokio::test]
+async fn create_vault_prefunded_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+    let seed = "prefunded";
+    let (pda, _) = find_vault_address(&dart.pubkey(), seed);
+
+    // A deposit lands on the vault address before the vault is created.
+    let deposit = 5_000_000;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &pda, deposit),
+            instruction::create_vault(
+                &context.payer.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                seed,
+                AuthorityMode::Custodial,
+                None,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, id());
+    assert_eq!(account.lamports, deposit);
+    let record = VaultRecord::unpack(&account.data).unwrap();
+    assert_eq!(record.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn create_vault_fail_wrong_dart() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let other_dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey(), other_dart.pubkey()]).await;
+    let seed = "U5f76katXToqua7SJzvP7";
+
+    // Address derived for one DART, but signed by another.
+    let mut create = instruction::create_vault(
+        &context.payer.pubkey(),
+        &dart.pubkey(),
+        &owner.pubkey(),
+        seed,
+        AuthorityMode::Custodial,
+        None,
+    );
+    create.accounts[2].pubkey = other_dart.pubkey();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[create],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &other_dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn create_vault_fail_non_canonical_bump() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+    let seed = "U5f76katXToqua7SJzvP7";
+
+    // Another bump of the same seed derives a second valid address.
+    let address_seed = vault_seed(&dart.pubkey(), seed);
+    let (_, canonical_bump) = find_vault_address(&dart.pubkey(), seed);
+    let (pda, bump) = (0..canonical_bump)
+        .rev()
+        .find_map(|bump| {
+            Pubkey::create_program_address(&[VAULT_SEED_PREFIX, &address_seed, &[bump]], &id())
+                .ok()
+                .map(|pda| (pda, bump))
+        })
+        .unwrap();
+
+    let mut create = instruction::create_vault(
+        &context.payer.pubkey(),
+        &dart.pubkey(),
+        &owner.pubkey(),
+        seed,
+        AuthorityMode::Custodial,
+        None,
+    );
+    create.accounts[0].pubkey = pda;
+    create.data = instruction::VaultInstruction::CreateVault {
+        seed: seed.to_string(),
+        bump,
+        mode: AuthorityMode::Custodial,
+        unlock_at: None,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[create],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn initialize_fail_unknown_intermediary() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[]).await;
+
+    let space = VaultRecord::LEN;
+    let lamports = Rent::default().minimum_balance(space);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &pda.pubkey(),
+                lamports,
+                space as u64,
+                &id(),
+            ),
+            instruction::initialize(&pda.pubkey(), &dart.pubkey(), &owner.pubkey(), None),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &pda, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::UnknownIntermediary as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn update_config_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let new_admin = Keypair::new();
+    initialize_config(&mut context, &[]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_config(
+            &context.payer.pubkey(),
+            Some(new_admin.pubkey()),
+            Some(vec![dart.pubkey()]),
+            None,
+            None,
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(find_config_address().0)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = ProgramConfig::unpack(&account.data).unwrap();
+    assert_eq!(config.admin, new_admin.pubkey());
+    assert_eq!(config.intermediaries, vec![dart.pubkey()]);
+}
+
+#[tokio::test]
+async fn update_config_fail_wrong_admin() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let wrong_admin = Keypair::new();
+    initialize_config(&mut context, &[]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_config(
+            &wrong_admin.pubkey(),
+            None,
+            Some(vec![dart.pubkey()]),
+            None,
+            None,
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_admin],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+// Helper: set the program fee schedule and collector.
+async fn set_fees(context: &mut ProgramTestContext, fees: FeeSchedule, fee_collector: &Pubkey) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_config(
+            &context.payer.pubkey(),
+            None,
+            None,
+            Some(fees),
+            Some(*fee_collector),
+            None,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn close_account_with_fee_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let fees = FeeSchedule {
+        basis_points: 100,
+        minimum: 0,
+    };
+    set_fees(&mut context, fees, &dart.pubkey()).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let deposit = 1_000_000_000;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &pda.pubkey(), deposit),
+            instruction::close_account(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &dart.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let total = deposit;
+    let fee = fees.fee_for(total).unwrap();
+    assert_eq!(fee, total / 100);
+
+    let recipient = context
+        .banks_client
+        .get_account(owner.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(recipient.lamports, total - fee);
+
+    let collector = context
+        .banks_client
+        .get_account(dart.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(collector.lamports, fee);
+}
+
+#[tokio::test]
+async fn withdraw_with_fee_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let fees = FeeSchedule {
+        basis_points: 10,
+        minimum: 1_000_000,
+    };
+    set_fees(&mut context, fees, &dart.pubkey()).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    // The flat minimum applies to small withdrawals.
+    let amount = 100_000_000;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &pda.pubkey(), amount),
+            instruction::withdraw(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &dart.pubkey(),
+                amount,
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let recipient = context
+        .banks_client
+        .get_account(owner.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(recipient.lamports, amount - fees.minimum);
+
+    let collector = context
+        .banks_client
+        .get_account(dart.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(collector.lamports, fees.minimum);
+}
+
+#[tokio::test]
+async fn close_account_fail_wrong_fee_collector() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &owner.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn propose_accept_owner_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let new_owner = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::propose_owner(
+            &pda.pubkey(),
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &new_owner.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // Ownership does not change until the new owner accepts.
+    let record = get_record(&mut context, pda.pubkey()).await;
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.pending_owner, Some(new_owner.pubkey()));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::accept_owner(
+            &pda.pubkey(),
+            &new_owner.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda.pubkey()).await;
+    assert_eq!(record.owner, new_owner.pubkey());
+    assert_eq!(record.pending_owner, None);
+}
+
+#[tokio::test]
+async fn accept_owner_fail_not_proposed() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let new_owner = Keypair::new();
+    let other = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_owner(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &new_owner.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+            instruction::accept_owner(&pda.pubkey(), &other.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart, &other],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn cancel_owner_proposal_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+
+    let new_owner = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::propose_owner(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &new_owner.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+            instruction::cancel_owner_proposal(
+                &pda.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda.pubkey()).await;
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.pending_owner, None);
+
+    // A cancelled proposal can no longer be accepted.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::accept_owner(
+            &pda.pubkey(),
+            &new_owner.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_owner],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::NoPendingOwner as u32)
+        )
+    );
+}
+
+// Helper: create a funded vault PDA with the given authority mode.
+async fn create_funded_vault(
+    context: &mut ProgramTestContext,
+    dart: &Keypair,
+    owner: &Keypair,
+    mode: AuthorityMode,
+    deposit: u64,
+) -> Pubkey {
+    let seed = "funded";
+    let (pda, _) = find_vault_address(&dart.pubkey(), seed);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::create_vault(
+                &context.payer.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                seed,
+                mode,
+                None,
+            ),
+            system_instruction::transfer(&context.payer.pubkey(), &pda, deposit),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    pda
+}
+
+// Helper: withdraw from a vault with the signers implied by a mode.
+async fn withdraw_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    amount: u64,
+    signers_mode: AuthorityMode,
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    if signers_mode.dart_signs() {
+        signers.push(dart);
+    }
+    if signers_mode.owner_signs() {
+        signers.push(owner);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::withdraw(
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            amount,
+            signers_mode,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn custodial_mode_requires_dart() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let mode = AuthorityMode::Custodial;
+    let pda = create_funded_vault(&mut context, &dart, &owner, mode, 10_000_000).await;
+
+    // The owner cannot act alone on a custodial vault.
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_000_000,
+        AuthorityMode::SelfCustody,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MissingSignature as u32)
+        )
+    );
+
+    withdraw_as(&mut context, &pda, &dart, &owner, 1_000_000, mode)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn self_custody_mode_requires_owner() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let mode = AuthorityMode::SelfCustody;
+    let pda = create_funded_vault(&mut context, &dart, &owner, mode, 10_000_000).await;
+
+    // The DART cannot act alone on a self-custody vault.
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MissingSignature as u32)
+        )
+    );
+
+    withdraw_as(&mut context, &pda, &dart, &owner, 1_000_000, mode)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn co_sign_mode_requires_dart_and_owner() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let mode = AuthorityMode::CoSign;
+    let pda = create_funded_vault(&mut context, &dart, &owner, mode, 10_000_000).await;
+
+    for signers_mode in [AuthorityMode::Custodial, AuthorityMode::SelfCustody] {
+        let err = withdraw_as(&mut context, &pda, &dart, &owner, 1_000_000, signers_mode)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::MissingSignature as u32)
+            )
+        );
+    }
+
+    withdraw_as(&mut context, &pda, &dart, &owner, 1_000_000, mode)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn set_authority_mode_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_authority_mode(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            AuthorityMode::SelfCustody,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart, &owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.authority_mode, AuthorityMode::SelfCustody);
+}
+
+// Helper: add a vault record account written with the version 1 layout.
+fn add_v1_vault(program_test: &mut ProgramTest, dart: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let mut data = vec![VaultRecord::V1_VERSION];
+    data.extend_from_slice(&owner.to_bytes());
+    data.extend_from_slice(&dart.to_bytes());
+
+    let pda = Pubkey::new_unique();
+    program_test.add_account(
+        pda,
+        Account {
+            lamports: Rent::default().minimum_balance(VaultRecord::V1_LEN),
+            data,
+            owner: id(),
+            ..Account::default()
+        },
+    );
+    pda
+}
+
+#[tokio::test]
+async fn migrate_v1_record_success() {
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    let mut program_test = program_test();
+    let pda = add_v1_vault(&mut program_test, &dart.pubkey(), &owner.pubkey());
+    let mut context = program_test.start_with_context().await;
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::migrate(&pda, &context.payer.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), VaultRecord::LEN);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(VaultRecord::LEN)
+    );
+    let record = VaultRecord::unpack(&account.data).unwrap();
+    assert_eq!(record.version, VaultRecord::CURRENT_VERSION);
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.dart, dart.pubkey());
+    assert_eq!(record.authority_mode, AuthorityMode::Custodial);
+
+    // The migrated vault is usable again
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_owner(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &new_owner.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_record(&mut context, pda).await.owner,
+        new_owner.pubkey()
+    );
+}
+
+#[tokio::test]
+async fn v1_record_fail_without_migration() {
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    let mut program_test = program_test();
+    let pda = add_v1_vault(&mut program_test, &dart.pubkey(), &owner.pubkey());
+    let mut context = program_test.start_with_context().await;
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_owner(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &new_owner.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OutdatedRecord as u32)
+        )
+    );
+}
+
+// Helper: freeze or thaw a vault, signed by the given DART.
+async fn set_frozen(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    frozen: bool,
+) -> Result<(), BanksClientError> {
+    let instruction = if frozen {
+        instruction::freeze(pda, &dart.pubkey())
+    } else {
+        instruction::thaw(pda, &dart.pubkey())
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn freeze_blocks_withdraw_until_thawed() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+
+    set_frozen(&mut context, &pda, &dart, true).await.unwrap();
+    assert!(get_record(&mut context, pda).await.frozen);
+
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::Frozen as u32))
+    );
+
+    set_frozen(&mut context, &pda, &dart, false).await.unwrap();
+    assert!(!get_record(&mut context, pda).await.frozen);
+
+    // A different amount, so the transaction does not repeat the rejected one
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn freeze_blocks_transfer_owner() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+    set_frozen(&mut context, &pda, &dart, true).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_owner(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &new_owner.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::Frozen as u32))
+    );
+}
+
+#[tokio::test]
+async fn freeze_fail_wrong_dart() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::SelfCustody, 0).await;
+
+    // The owner cannot freeze or thaw, even of a self-custodied vault
+    assert_eq!(
+        set_frozen(&mut context, &pda, &owner, true)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn pause_blocks_close_account() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::update_config(
+            &context.payer.pubkey(),
+            None,
+            None,
+            None,
+            None,
+            Some(true),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::Frozen as u32))
+    );
+}
+
+// Helper: create a custodial vault with a time lock and an initial deposit.
+async fn create_locked_vault(
+    context: &mut ProgramTestContext,
+    dart: &Keypair,
+    owner: &Keypair,
+    unlock_at: UnlockAt,
+    deposit: u64,
+) -> Pubkey {
+    let seed = "locked";
+    let (pda, _) = find_vault_address(&dart.pubkey(), seed);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::create_vault(
+                &context.payer.pubkey(),
+                &dart.pubkey(),
+                &owner.pubkey(),
+                seed,
+                AuthorityMode::Custodial,
+                Some(unlock_at),
+            ),
+            system_instruction::transfer(&context.payer.pubkey(), &pda, deposit),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    pda
+}
+
+#[tokio::test]
+async fn slot_lock_blocks_withdraw_until_expired() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_locked_vault(
+        &mut context,
+        &dart,
+        &owner,
+        UnlockAt::Slot(1_000),
+        10_000_000,
+    )
+    .await;
+
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::Locked as u32))
+    );
+
+    context.warp_to_slot(1_000).unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn timestamp_lock_blocks_close_account() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_locked_vault(
+        &mut context,
+        &dart,
+        &owner,
+        UnlockAt::Timestamp(i64::MAX),
+        0,
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::Custom(VaultError::Locked as u32))
+    );
+}
+
+#[tokio::test]
+async fn extend_lock_only_later() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_locked_vault(&mut context, &dart, &owner, UnlockAt::Slot(1_000), 0).await;
+
+    // Neither shortening the lock nor switching to timestamps is allowed
+    for unlock_at in [UnlockAt::Slot(999), UnlockAt::Timestamp(i64::MAX)] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::extend_lock(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                unlock_at,
+                AuthorityMode::Custodial,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &dart],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InvalidLock as u32)
+            )
+        );
+    }
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::extend_lock(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            UnlockAt::Slot(2_000),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.unlock_at, Some(UnlockAt::Slot(2_000)));
+}
+
+// Helper: create and initialize a multisig account.
+async fn create_multisig(
+    context: &mut ProgramTestContext,
+    threshold: u8,
+    signers: &[Pubkey],
+) -> Result<Pubkey, BanksClientError> {
+    let multisig = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig.pubkey(),
+                Rent::default().minimum_balance(Multisig::LEN),
+                Multisig::LEN as u64,
+                &id(),
+            ),
+            instruction::initialize_multisig(&multisig.pubkey(), threshold, signers.to_vec()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &multisig],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map(|_| multisig.pubkey())
+}
+
+#[tokio::test]
+async fn multisig_dart_withdraw_threshold() {
+    let mut context = program_test().start_with_context().await;
+
+    let owner = Keypair::new();
+    let keys = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signers: Vec<Pubkey> = keys.iter().map(|key| key.pubkey()).collect();
+    let multisig = create_multisig(&mut context, 2, &signers).await.unwrap();
+    initialize_config(&mut context, &[multisig]).await;
+
+    // Create a vault with the multisig as DART
+    let seed = "multisig";
+    let (pda, _) = find_vault_address(&multisig, seed);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::with_multisig_signers(
+                instruction::create_vault(
+                    &context.payer.pubkey(),
+                    &multisig,
+                    &owner.pubkey(),
+                    seed,
+                    AuthorityMode::Custodial,
+                    None,
+                ),
+                &multisig,
+                &signers[..2],
+            ),
+            system_instruction::transfer(&context.payer.pubkey(), &pda, 10_000_000),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &keys[0], &keys[1]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(get_record(&mut context, pda).await.dart, multisig);
+
+    let withdraw = |approvers: &[Pubkey]| {
+        instruction::with_multisig_signers(
+            instruction::withdraw(
+                &pda,
+                &multisig,
+                &owner.pubkey(),
+                &context.payer.pubkey(),
+                2_000_000,
+                AuthorityMode::Custodial,
+            ),
+            &multisig,
+            approvers,
+        )
+    };
+
+    // A single approval is below the threshold
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw(&signers[..1])],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &keys[0]],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MissingSignature as u32)
+        )
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw(&[signers[0], signers[2]])],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &keys[0], &keys[2]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn initialize_multisig_fail_threshold() {
+    let mut context = program_test().start_with_context().await;
+
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    assert_eq!(
+        create_multisig(&mut context, 3, &signers)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::InvalidMultisig as u32)
+        )
+    );
+}
+
+// Helper: rotate a vault to a new intermediary, signed by the given authority.
+async fn rotate_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    authority: &Keypair,
+    new_dart: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    if authority.pubkey() != context.payer.pubkey() {
+        signers.push(authority);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::rotate_intermediary(
+            pda,
+            &authority.pubkey(),
+            new_dart,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn rotate_intermediary_by_dart_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let new_dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey(), new_dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+
+    rotate_as(&mut context, &pda, &dart, &new_dart.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(get_record(&mut context, pda).await.dart, new_dart.pubkey());
+
+    // Only the new DART can act for the vault
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+    withdraw_as(
+        &mut context,
+        &pda,
+        &new_dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn rotate_intermediary_after_migration_success() {
+    let dart = Keypair::new();
+    let new_dart = Keypair::new();
+    let owner = Keypair::new();
+    let mut program_test = program_test();
+    let v1_pda = add_v1_vault(&mut program_test, &dart.pubkey(), &owner.pubkey());
+    let mut context = program_test.start_with_context().await;
+    initialize_config(&mut context, &[dart.pubkey(), new_dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+
+    // Migration is a no-op on the current record, so a batch can migrate
+    // every vault before rotating it.
+    let instructions: Vec<Instruction> = [v1_pda, pda]
+        .iter()
+        .flat_map(|pda| {
+            [
+                instruction::migrate(pda, &context.payer.pubkey()),
+                instruction::rotate_intermediary(pda, &dart.pubkey(), &new_dart.pubkey()),
+            ]
+        })
+        .collect();
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    for pda in [v1_pda, pda] {
+        let record = get_record(&mut context, pda).await;
+        assert_eq!(record.version, VaultRecord::CURRENT_VERSION);
+        assert_eq!(record.dart, new_dart.pubkey());
+    }
+}
+
+#[tokio::test]
+async fn rotate_intermediary_by_admin_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let new_dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey(), new_dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    // The payer is the config admin
+    let admin = context.payer.insecure_clone();
+    rotate_as(&mut context, &pda, &admin, &new_dart.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(get_record(&mut context, pda).await.dart, new_dart.pubkey());
+}
+
+#[tokio::test]
+async fn rotate_intermediary_fail_wrong_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let new_dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey(), new_dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::SelfCustody, 0).await;
+
+    assert_eq!(
+        rotate_as(&mut context, &pda, &owner, &new_dart.pubkey())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn rotate_intermediary_fail_unknown_intermediary() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(&mut context, &dart, &owner, AuthorityMode::Custodial, 0).await;
+
+    assert_eq!(
+        rotate_as(&mut context, &pda, &dart, &Pubkey::new_unique())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::UnknownIntermediary as u32)
+        )
+    );
+}
+
+// Helper: transfer ownership of a custodial vault.
+async fn transfer_owner_custodial(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Pubkey,
+    new_owner: &Pubkey,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_owner(
+            pda,
+            &dart.pubkey(),
+            owner,
+            new_owner,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+// Helper: approve a delegate on a custodial vault.
+#[allow(clippy::too_many_arguments)]
+async fn approve_delegate(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    expires_at: Option<i64>,
+    recipient: &Pubkey,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::approve(
+            &context.payer.pubkey(),
+            pda,
+            &dart.pubkey(),
+            owner,
+            delegate,
+            amount,
+            expires_at,
+            recipient,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+// Helper: withdraw from a vault as a delegate.
+async fn delegated_withdraw_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    delegate: &Keypair,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::delegated_withdraw(
+            pda,
+            &delegate.pubkey(),
+            recipient,
+            &context.payer.pubkey(),
+            amount,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, delegate],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn delegated_withdraw_draws_down_allowance() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let delegate = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    approve_delegate(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &delegate.pubkey(),
+        3_000_000,
+        None,
+        &recipient,
+    )
+    .await;
+
+    delegated_withdraw_as(&mut context, &pda, &delegate, &recipient, 2_000_000)
+        .await
+        .unwrap();
+
+    let (address, _) = find_allowance_address(&pda, &delegate.pubkey());
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    let allowance = Allowance::unpack(&account.data).unwrap();
+    assert_eq!(allowance.amount, 1_000_000);
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        2_000_000
+    );
+
+    assert_eq!(
+        delegated_withdraw_as(&mut context, &pda, &delegate, &recipient, 1_500_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::AllowanceExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegated_withdraw_fail_expired() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let delegate = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    approve_delegate(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &delegate.pubkey(),
+        3_000_000,
+        Some(1),
+        &recipient,
+    )
+    .await;
+
+    assert_eq!(
+        delegated_withdraw_as(&mut context, &pda, &delegate, &recipient, 2_000_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::AllowanceExpired as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegated_withdraw_fail_after_owner_change() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Pubkey::new_unique();
+    let delegate = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    approve_delegate(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &delegate.pubkey(),
+        3_000_000,
+        None,
+        &recipient,
+    )
+    .await;
+    transfer_owner_custodial(&mut context, &pda, &dart, &owner.pubkey(), &new_owner).await;
+
+    assert_eq!(
+        delegated_withdraw_as(&mut context, &pda, &delegate, &recipient, 2_000_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OwnerChanged as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegated_withdraw_fail_wrong_recipient() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let delegate = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    approve_delegate(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &delegate.pubkey(),
+        3_000_000,
+        None,
+        &Pubkey::new_unique(),
+    )
+    .await;
+
+    assert_eq!(
+        delegated_withdraw_as(
+            &mut context,
+            &pda,
+            &delegate,
+            &Pubkey::new_unique(),
+            2_000_000
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WrongRecipient as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn revoke_closes_allowance() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let delegate = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    approve_delegate(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &delegate.pubkey(),
+        3_000_000,
+        None,
+        &Pubkey::new_unique(),
+    )
+    .await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::revoke(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &delegate.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let (address, _) = find_allowance_address(&pda, &delegate.pubkey());
+    assert!(context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        delegated_withdraw_as(
+            &mut context,
+            &pda,
+            &delegate,
+            &Pubkey::new_unique(),
+            2_000_000
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidAccountOwner as u32)
+        )
+    );
+}
+
+// Helper: set the withdrawal limit of a vault.
+async fn set_limit_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    authority: &Keypair,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let limit = WithdrawalLimit {
+        amount,
+        window: 24 * 60 * 60,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_withdrawal_limit(
+            pda,
+            &authority.pubkey(),
+            Some(limit),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn withdrawal_limit_blocks_excess() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_limit_as(&mut context, &pda, &dart, 3_000_000)
+        .await
+        .unwrap();
+
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_500_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WithdrawalLimitExceeded as u32)
+        )
+    );
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    assert_eq!(get_record(&mut context, pda).await.withdrawn, 3_000_000);
+}
+
+#[tokio::test]
+async fn withdrawal_limit_raise_is_delayed() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_limit_as(&mut context, &pda, &dart, 1_000_000)
+        .await
+        .unwrap();
+    set_limit_as(&mut context, &pda, &dart, 5_000_000)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.limit.unwrap().amount, 1_000_000);
+    assert_eq!(
+        record.pending_limit.unwrap().limit.unwrap().amount,
+        5_000_000
+    );
+
+    let err = withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_000_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WithdrawalLimitExceeded as u32)
+        )
+    );
+
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += WithdrawalLimit::RAISE_DELAY;
+    context.set_sysvar(&clock);
+
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        2_500_000,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.limit.unwrap().amount, 5_000_000);
+    assert_eq!(record.pending_limit, None);
+}
+
+#[tokio::test]
+async fn withdrawal_limit_owner_can_only_lower() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_limit_as(&mut context, &pda, &owner, 3_000_000)
+        .await
+        .unwrap();
+    set_limit_as(&mut context, &pda, &owner, 2_000_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_record(&mut context, pda).await.limit.unwrap().amount,
+        2_000_000
+    );
+
+    assert_eq!(
+        set_limit_as(&mut context, &pda, &owner, 4_000_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MissingSignature as u32)
+        )
+    );
+}
+
+// Helper: allowlist a withdrawal destination, signed by the owner and DART.
+async fn add_destination(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    destination: &Pubkey,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::add_destination(
+            &context.payer.pubkey(),
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            destination,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart, owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+// Helper: withdraw from a custodial vault to a destination.
+async fn withdraw_to_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::withdraw_to(
+            pda,
+            &dart.pubkey(),
+            owner,
+            destination,
+            &context.payer.pubkey(),
+            amount,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Helper: advance the clock past the cooling-off period of new destinations.
+async fn wait_cooling_off(context: &mut ProgramTestContext) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += Destination::COOLING_OFF;
+    context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+async fn withdraw_to_after_cooling_off() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let destination = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    add_destination(&mut context, &pda, &dart, &owner, &destination).await;
+
+    let err = withdraw_to_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &destination,
+        2_000_000,
+    )
+    .await
+    .unwrap_err()
+    .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::DestinationCoolingOff as u32)
+        )
+    );
+
+    wait_cooling_off(&mut context).await;
+    withdraw_to_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &destination,
+        2_500_000,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        2_500_000
+    );
+}
+
+#[tokio::test]
+async fn withdraw_to_fail_after_owner_change() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let new_owner = Keypair::new();
+    let destination = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    add_destination(&mut context, &pda, &dart, &owner, &destination).await;
+    wait_cooling_off(&mut context).await;
+    transfer_owner_custodial(
+        &mut context,
+        &pda,
+        &dart,
+        &owner.pubkey(),
+        &new_owner.pubkey(),
+    )
+    .await;
+
+    assert_eq!(
+        withdraw_to_as(
+            &mut context,
+            &pda,
+            &dart,
+            &new_owner.pubkey(),
+            &destination,
+            2_000_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OwnerChanged as u32)
+        )
+    );
+
+    // The new owner may allowlist the destination again, cooling off anew.
+    add_destination(&mut context, &pda, &dart, &new_owner, &destination).await;
+    wait_cooling_off(&mut context).await;
+    withdraw_to_as(
+        &mut context,
+        &pda,
+        &dart,
+        &new_owner.pubkey(),
+        &destination,
+        2_500_000,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn withdraw_to_fail_unlisted_destination() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+
+    assert_eq!(
+        withdraw_to_as(
+            &mut context,
+            &pda,
+            &dart,
+            &owner.pubkey(),
+            &Pubkey::new_unique(),
+            2_000_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::DestinationNotAllowed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn remove_destination_blocks_withdraw_to() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let destination = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    add_destination(&mut context, &pda, &dart, &owner, &destination).await;
+    wait_cooling_off(&mut context).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::remove_destination(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &destination,
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart, &owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        withdraw_to_as(
+            &mut context,
+            &pda,
+            &dart,
+            &owner.pubkey(),
+            &destination,
+            2_000_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::DestinationNotAllowed as u32)
+        )
+    );
+}
+
+// Helper: move lamports between two custodial vaults.
+async fn internal_transfer_as(
+    context: &mut ProgramTestContext,
+    from: &Pubkey,
+    to: &Pubkey,
+    dart: &Keypair,
+    owner: &Pubkey,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::internal_transfer(
+            from,
+            to,
+            &dart.pubkey(),
+            owner,
+            amount,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn internal_transfer_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let other = Keypair::new();
+    let to = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let from = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    initialize_account(&mut context, &to, &dart, &other).await;
+
+    let rent = Rent::default().minimum_balance(VaultRecord::LEN);
+    internal_transfer_as(
+        &mut context,
+        &from,
+        &to.pubkey(),
+        &dart,
+        &owner.pubkey(),
+        4_000_000,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(from).await.unwrap(),
+        rent + 6_000_000
+    );
+    assert_eq!(
+        context.banks_client.get_balance(to.pubkey()).await.unwrap(),
+        rent + 4_000_000
+    );
+
+    // The source must stay rent-exempt.
+    assert_eq!(
+        internal_transfer_as(
+            &mut context,
+            &from,
+            &to.pubkey(),
+            &dart,
+            &owner.pubkey(),
+            6_000_001,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InsufficientFunds as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn internal_transfer_fail_other_intermediary() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let other_dart = Keypair::new();
+    let owner = Keypair::new();
+    let to = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey(), other_dart.pubkey()]).await;
+
+    let from = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    initialize_account(&mut context, &to, &other_dart, &owner).await;
+
+    assert_eq!(
+        internal_transfer_as(
+            &mut context,
+            &from,
+            &to.pubkey(),
+            &dart,
+            &owner.pubkey(),
+            1_000_000,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+// Helper: withdraw from a co-signed vault with an off-chain owner approval.
+async fn withdraw_approved(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    approval: &OwnerApproval,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let signature = owner.sign_message(&approval.message()).into();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            approval.verify_instruction(&owner.pubkey(), &signature),
+            instruction::with_owner_approval(
+                instruction::withdraw(
+                    pda,
+                    &dart.pubkey(),
+                    &owner.pubkey(),
+                    &context.payer.pubkey(),
+                    amount,
+                    AuthorityMode::CoSign,
+                ),
+                &owner.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, dart],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn withdraw_with_owner_approval_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::CoSign,
+        10_000_000,
+    )
+    .await;
+    let approval = OwnerApproval {
+        program_id: id(),
+        vault: pda,
+        action: ApprovalAction::Withdraw,
+        destination: owner.pubkey(),
+        amount: 2_000_000,
+        nonce: 1,
+    };
+    withdraw_approved(&mut context, &pda, &dart, &owner, &approval, 2_000_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(owner.pubkey())
+            .await
+            .unwrap(),
+        2_000_000
+    );
+    assert_eq!(get_record(&mut context, pda).await.nonce, 1);
+
+    // The same approval cannot be replayed.
+    context.last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    assert_eq!(
+        withdraw_approved(&mut context, &pda, &dart, &owner, &approval, 2_000_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::StaleApproval as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn withdraw_with_owner_approval_fail_wrong_amount() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::CoSign,
+        10_000_000,
+    )
+    .await;
+    let approval = OwnerApproval {
+        program_id: id(),
+        vault: pda,
+        action: ApprovalAction::Withdraw,
+        destination: owner.pubkey(),
+        amount: 1_000_000,
+        nonce: 1,
+    };
+    assert_eq!(
+        withdraw_approved(&mut context, &pda, &dart, &owner, &approval, 2_000_000)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::InvalidApproval as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn withdraw_to_with_owner_approval_fail_wrong_destination() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let approved = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::CoSign,
+        10_000_000,
+    )
+    .await;
+    add_destination(&mut context, &pda, &dart, &owner, &approved).await;
+    add_destination(&mut context, &pda, &dart, &owner, &other).await;
+    wait_cooling_off(&mut context).await;
+
+    let approval = OwnerApproval {
+        program_id: id(),
+        vault: pda,
+        action: ApprovalAction::WithdrawTo,
+        destination: approved,
+        amount: 2_000_000,
+        nonce: 1,
+    };
+    let signature = owner.sign_message(&approval.message()).into();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            approval.verify_instruction(&owner.pubkey(), &signature),
+            instruction::with_owner_approval(
+                instruction::withdraw_to(
+                    &pda,
+                    &dart.pubkey(),
+                    &owner.pubkey(),
+                    &other,
+                    &context.payer.pubkey(),
+                    2_000_000,
+                    AuthorityMode::CoSign,
+                ),
+                &owner.pubkey(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(VaultError::InvalidApproval as u32)
+        )
+    );
+}
+
+// Helper: set the guardian of a vault, signed per its authority mode.
+async fn set_guardian_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    guardian: &Pubkey,
+    mode: AuthorityMode,
+) {
+    let mut signers = vec![&context.payer];
+    if mode.dart_signs() {
+        signers.push(dart);
+    }
+    if mode.owner_signs() {
+        signers.push(owner);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_guardian(
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            Some(*guardian),
+            mode,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+// Helper: start recovering a vault to a new owner.
+async fn start_recovery_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    guardian: &Keypair,
+    new_owner: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::start_recovery(
+            pda,
+            &guardian.pubkey(),
+            new_owner,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, guardian],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Helper: complete a vault recovery.
+async fn complete_recovery(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::complete_recovery(pda)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn recovery_completes_after_challenge_period() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let guardian = Keypair::new();
+    let new_owner = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_guardian_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &guardian.pubkey(),
+        AuthorityMode::Custodial,
+    )
+    .await;
+    start_recovery_as(&mut context, &pda, &guardian, &new_owner)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        complete_recovery(&mut context, &pda)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::RecoveryNotReady as u32)
+        )
+    );
+
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += Recovery::CHALLENGE_PERIOD;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    complete_recovery(&mut context, &pda).await.unwrap();
+
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.owner, new_owner);
+    assert_eq!(record.recovery, None);
+}
+
+#[tokio::test]
+async fn recovery_cancelled_by_owner() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let guardian = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::SelfCustody,
+        10_000_000,
+    )
+    .await;
+    set_guardian_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &guardian.pubkey(),
+        AuthorityMode::SelfCustody,
+    )
+    .await;
+    start_recovery_as(&mut context, &pda, &guardian, &Pubkey::new_unique())
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::cancel_recovery(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            AuthorityMode::SelfCustody,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += Recovery::CHALLENGE_PERIOD;
+    context.set_sysvar(&clock);
+    assert_eq!(
+        complete_recovery(&mut context, &pda)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::NoPendingRecovery as u32)
+        )
+    );
+    assert_eq!(get_record(&mut context, pda).await.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn recovery_cancelled_by_owner_of_custodial_vault() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let guardian = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_guardian_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &guardian.pubkey(),
+        AuthorityMode::Custodial,
+    )
+    .await;
+    start_recovery_as(&mut context, &pda, &guardian, &Pubkey::new_unique())
+        .await
+        .unwrap();
+
+    // The owner cancels without the DART.
+    let mut cancel = instruction::cancel_recovery(
+        &pda,
+        &dart.pubkey(),
+        &owner.pubkey(),
+        AuthorityMode::Custodial,
+    );
+    cancel.accounts[2].is_signer = false;
+    cancel.accounts[3].is_signer = true;
+    let transaction = Transaction::new_signed_with_payer(
+        &[cancel],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &owner],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(get_record(&mut context, pda).await.recovery, None);
+}
+
+#[tokio::test]
+async fn start_recovery_fail_pending() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let guardian = Keypair::new();
+    let new_owner = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_guardian_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &guardian.pubkey(),
+        AuthorityMode::Custodial,
+    )
+    .await;
+    start_recovery_as(&mut context, &pda, &guardian, &new_owner)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        start_recovery_as(&mut context, &pda, &guardian, &Pubkey::new_unique())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::RecoveryPending as u32)
+        )
+    );
+    assert_eq!(
+        get_record(&mut context, pda)
+            .await
+            .recovery
+            .unwrap()
+            .new_owner,
+        new_owner
+    );
+}
+
+#[tokio::test]
+async fn start_recovery_fail_wrong_guardian() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let guardian = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_guardian_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &guardian.pubkey(),
+        AuthorityMode::Custodial,
+    )
+    .await;
+
+    assert_eq!(
+        start_recovery_as(&mut context, &pda, &Keypair::new(), &Pubkey::new_unique())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+// Helper: create a mint under a token program, with the payer as mint authority.
+async fn create_mint(context: &mut ProgramTestContext, token_program: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let space = spl_token_2022::state::Mint::LEN;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &mint.pubkey(),
+                Rent::default().minimum_balance(space),
+                space as u64,
+                token_program,
+            ),
+            spl_token_2022::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &context.payer.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &mint],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    mint.pubkey()
+}
+
+// Helper: get the balance of a token account.
+async fn get_token_balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+// Open a vault token account, withdraw its tokens to the owner and close it.
+async fn token_custody(token_program: Pubkey) {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let mint = create_mint(&mut context, &token_program).await;
+    let vault_tokens = get_associated_token_address_with_program_id(&pda, &mint, &token_program);
+    let owner_tokens =
+        get_associated_token_address_with_program_id(&owner.pubkey(), &mint, &token_program);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::open_token_account(&context.payer.pubkey(), &pda, &mint, &token_program),
+            spl_token_2022::instruction::mint_to(
+                &token_program,
+                &mint,
+                &vault_tokens,
+                &context.payer.pubkey(),
+                &[],
+                1_000,
+            )
+            .unwrap(),
+            create_associated_token_account(
+                &context.payer.pubkey(),
+                &owner.pubkey(),
+                &mint,
+                &token_program,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::withdraw_token(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &mint,
+            &token_program,
+            1_000,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(get_token_balance(&mut context, vault_tokens).await, 0);
+    assert_eq!(get_token_balance(&mut context, owner_tokens).await, 1_000);
+
+    // The vault cannot close while its token account is open.
+    let close = instruction::close_account(
+        &pda,
+        &dart.pubkey(),
+        &owner.pubkey(),
+        &context.payer.pubkey(),
+        AuthorityMode::Custodial,
+    );
+    assert_eq!(get_record(&mut context, pda).await.open_accounts, 1);
+    let transaction = Transaction::new_signed_with_payer(
+        std::slice::from_ref(&close),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OpenAccounts as u32)
+        )
+    );
+
+    // Closing the emptied token account returns its rent to the vault.
+    let vault_lamports = context.banks_client.get_balance(pda).await.unwrap();
+    let rent = context
+        .banks_client
+        .get_balance(vault_tokens)
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_token_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &mint,
+            &token_program,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(vault_tokens)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client.get_balance(pda).await.unwrap(),
+        vault_lamports + rent
+    );
+    assert_eq!(get_record(&mut context, pda).await.open_accounts, 0);
+
+    context.last_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[close],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn token_custody_spl_token() {
+    token_custody(spl_token::id()).await;
+}
+
+#[tokio::test]
+async fn token_custody_token_2022() {
+    token_custody(spl_token_2022::id()).await;
+}
+
+#[tokio::test]
+async fn open_token_account_fail_not_program_address() {
+    let mut context = program_test().start_with_context().await;
+
+    let pda = Keypair::new();
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+    initialize_account(&mut context, &pda, &dart, &owner).await;
+    let mint = create_mint(&mut context, &spl_token::id()).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::open_token_account(
+            &context.payer.pubkey(),
+            &pda.pubkey(),
+            &mint,
+            &spl_token::id(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::NotProgramAddress as u32)
+        )
+    );
+}
+
+// Helper: create a vote account for a new validator.
+async fn create_vote_account(context: &mut ProgramTestContext) -> Pubkey {
+    let vote = Keypair::new();
+    let node = Keypair::new();
+    let space = VoteState::size_of();
+    let transaction = Transaction::new_signed_with_payer(
+        &vote_instruction::create_account_with_config(
+            &context.payer.pubkey(),
+            &vote.pubkey(),
+            &VoteInit {
+                node_pubkey: node.pubkey(),
+                authorized_voter: node.pubkey(),
+                authorized_withdrawer: node.pubkey(),
+                commission: 0,
+            },
+            Rent::default().minimum_balance(space),
+            CreateVoteAccountConfig {
+                space: space as u64,
+                ..CreateVoteAccountConfig::default()
+            },
+        ),
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &vote, &node],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    vote.pubkey()
+}
+
+#[tokio::test]
+async fn stake_round_trip() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        2_000_000_000,
+    )
+    .await;
+    let vote = create_vote_account(&mut context).await;
+    let (stake_account, _) = find_stake_address(&pda, 0);
+    let vault_lamports = context.banks_client.get_balance(pda).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::create_stake(
+                &context.payer.pubkey(),
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                0,
+                1_000_000_000,
+                AuthorityMode::Custodial,
+            ),
+            instruction::delegate_stake(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &stake_account,
+                &vote,
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(pda).await.unwrap(),
+        vault_lamports - 1_000_000_000
+    );
+
+    let account = context
+        .banks_client
+        .get_account(stake_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let stake_state: StakeStateV2 = account.deserialize_data().unwrap();
+    let rent = account.lamports - 1_000_000_000;
+    assert_eq!(stake_state.authorized().unwrap().withdrawer, pda);
+    assert_eq!(stake_state.delegation().unwrap().voter_pubkey, vote);
+
+    // The vault cannot close while its stake account is open.
+    assert_eq!(get_record(&mut context, pda).await.open_accounts, 1);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OpenAccounts as u32)
+        )
+    );
+
+    // Deactivated in the epoch it was delegated, the stake is withdrawable
+    // right away.
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::deactivate_stake(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &stake_account,
+                AuthorityMode::Custodial,
+            ),
+            instruction::withdraw_stake(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &stake_account,
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(stake_account)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        context.banks_client.get_balance(pda).await.unwrap(),
+        vault_lamports + rent
+    );
+    assert_eq!(get_record(&mut context, pda).await.open_accounts, 0);
+}
+
+#[tokio::test]
+async fn create_stake_fail_wrong_stake_address() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        2_000_000_000,
+    )
+    .await;
+
+    // The instruction derives the stake address for index 1, the program
+    // for index 0.
+    let mut create_stake = instruction::create_stake(
+        &context.payer.pubkey(),
+        &pda,
+        &dart.pubkey(),
+        &owner.pubkey(),
+        0,
+        1_000_000_000,
+        AuthorityMode::Custodial,
+    );
+    create_stake.accounts[5].pubkey = find_stake_address(&pda, 1).0;
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_stake],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+// Helper: make a vault co-owned by the given co-owners, holding one and three
+// shares.
+async fn set_co_owners_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    co_owners: &[Pubkey; 2],
+    threshold: u8,
+    mode: AuthorityMode,
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    if mode.dart_signs() {
+        signers.push(dart);
+    }
+    if mode.owner_signs() {
+        signers.push(owner);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_co_owners(
+            &context.payer.pubkey(),
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            vec![
+                CoOwner {
+                    owner: co_owners[0],
+                    shares: 1,
+                },
+                CoOwner {
+                    owner: co_owners[1],
+                    shares: 3,
+                },
+            ],
+            threshold,
+            mode,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn co_owned_vault_pays_out_pro_rata() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let wallets = [Pubkey::new_unique(), Pubkey::new_unique()];
+    set_co_owners_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &wallets,
+        1,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    let (co_owners, _) = find_co_owners_address(&pda);
+    assert_eq!(get_record(&mut context, pda).await.owner, co_owners);
+
+    // Withdrawals are split 1:3 between the co-owners.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_co_owner_wallets(
+            instruction::withdraw(
+                &pda,
+                &dart.pubkey(),
+                &co_owners,
+                &context.payer.pubkey(),
+                4_000_000,
+                AuthorityMode::Custodial,
+            ),
+            &wallets,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(wallets[0]).await.unwrap(),
+        1_000_000
+    );
+    assert_eq!(
+        context.banks_client.get_balance(wallets[1]).await.unwrap(),
+        3_000_000
+    );
+
+    // Closing pays out the rest, with the co-owner list rent, in one go.
+    let remaining = context.banks_client.get_balance(pda).await.unwrap()
+        + context.banks_client.get_balance(co_owners).await.unwrap()
+        - Rent::default().minimum_balance(VaultRecord::LEN);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::with_co_owner_wallets(
+            instruction::close_account(
+                &pda,
+                &dart.pubkey(),
+                &co_owners,
+                &context.payer.pubkey(),
+                AuthorityMode::Custodial,
+            ),
+            &wallets,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let first = context.banks_client.get_balance(wallets[0]).await.unwrap() - 1_000_000;
+    let second = context.banks_client.get_balance(wallets[1]).await.unwrap() - 3_000_000;
+    assert_eq!(first + second, remaining);
+    assert_eq!(second, remaining / 4 * 3);
+    assert!(context
+        .banks_client
+        .get_account(co_owners)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn co_owned_vault_requires_threshold() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::SelfCustody,
+        10_000_000,
+    )
+    .await;
+    let first = Keypair::new();
+    let second = Keypair::new();
+    let wallets = [first.pubkey(), second.pubkey()];
+    set_co_owners_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &wallets,
+        2,
+        AuthorityMode::SelfCustody,
+    )
+    .await
+    .unwrap();
+    let (co_owners, _) = find_co_owners_address(&pda);
+
+    let withdraw = |signers: &[Pubkey]| {
+        instruction::with_co_owner_wallets(
+            instruction::with_multisig_signers(
+                instruction::withdraw(
+                    &pda,
+                    &dart.pubkey(),
+                    &co_owners,
+                    &context.payer.pubkey(),
+                    4_000_000,
+                    AuthorityMode::SelfCustody,
+                ),
+                &co_owners,
+                signers,
+            ),
+            &wallets,
+        )
+    };
+
+    // One co-owner alone is below the threshold.
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw(&[first.pubkey()])],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &first],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MissingSignature as u32)
+        )
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[withdraw(&wallets)],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &first, &second],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(second.pubkey())
+            .await
+            .unwrap(),
+        3_000_000
+    );
+}
+
+#[tokio::test]
+async fn co_owner_transfers_share() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let wallets = [Pubkey::new_unique(), Pubkey::new_unique()];
+    set_co_owners_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        &wallets,
+        1,
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    let (co_owners, _) = find_co_owners_address(&pda);
+
+    let new_co_owner = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_share(
+            &pda,
+            &dart.pubkey(),
+            &wallets[1],
+            &new_co_owner,
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(co_owners)
+        .await
+        .unwrap()
+        .unwrap();
+    let list = CoOwners::unpack(&account.data).unwrap();
+    assert_eq!(
+        list.owners[1],
+        CoOwner {
+            owner: new_co_owner,
+            shares: 3,
+        }
+    );
+    assert_eq!(get_record(&mut context, pda).await.owner, co_owners);
+
+    // A former co-owner no longer holds a share.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_share(
+            &pda,
+            &dart.pubkey(),
+            &wallets[1],
+            &Pubkey::new_unique(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WrongOwner as u32)
+        )
+    );
+
+    // Transferring ownership never falls back to transferring a share.
+    let mut transfer = instruction::transfer_owner(
+        &pda,
+        &dart.pubkey(),
+        &new_co_owner,
+        &Pubkey::new_unique(),
+        AuthorityMode::Custodial,
+    );
+    transfer.accounts.push(AccountMeta::new(co_owners, false));
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::WrongOwner as u32)
+        )
+    );
+
+    // Nor can the co-owner list hand over the whole vault.
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::transfer_owner(
+            &pda,
+            &dart.pubkey(),
+            &co_owners,
+            &Pubkey::new_unique(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidCoOwners as u32)
+        )
+    );
+    assert_eq!(get_record(&mut context, pda).await.owner, co_owners);
+}
+
+#[tokio::test]
+async fn set_co_owners_fail_duplicate() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let co_owner = Pubkey::new_unique();
+    assert_eq!(
+        set_co_owners_as(
+            &mut context,
+            &pda,
+            &dart,
+            &owner,
+            &[co_owner, co_owner],
+            1,
+            AuthorityMode::Custodial,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidCoOwners as u32)
+        )
+    );
+}
+
+// Helper: set the beneficiary of a vault, signed per its authority mode.
+async fn set_beneficiary_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    inheritance: Option<Inheritance>,
+    mode: AuthorityMode,
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    if mode.dart_signs() {
+        signers.push(dart);
+    }
+    if mode.owner_signs() {
+        signers.push(owner);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_beneficiary(
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            inheritance,
+            mode,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Helper: claim an inactive vault as its beneficiary.
+async fn claim_inactive_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    beneficiary: &Keypair,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::claim_inactive(pda, &beneficiary.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, beneficiary],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Helper: warp ahead by a number of seconds, returning the new clock. The
+// test validator does not move its timestamp along with warped slots, so the
+// clock is set to match.
+async fn warp_seconds(context: &mut ProgramTestContext, seconds: i64) -> Clock {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let slots = seconds as u64 * 1_000 / DEFAULT_MS_PER_SLOT;
+    context.warp_to_slot(clock.slot + slots).unwrap();
+    let mut warped: Clock = context.banks_client.get_sysvar().await.unwrap();
+    warped.unix_timestamp = clock.unix_timestamp + seconds;
+    context.set_sysvar(&warped);
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    warped
+}
+
+/// Inactivity period for tests, in seconds
+const TEST_INACTIVITY_PERIOD: i64 = 3_600;
+
+#[tokio::test]
+async fn inactive_vault_claimed_by_beneficiary() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let beneficiary = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::SelfCustody,
+        10_000_000,
+    )
+    .await;
+    set_beneficiary_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        Some(Inheritance {
+            beneficiary: beneficiary.pubkey(),
+            inactivity_period: TEST_INACTIVITY_PERIOD,
+        }),
+        AuthorityMode::SelfCustody,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        claim_inactive_as(&mut context, &pda, &beneficiary)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OwnerNotInactive as u32)
+        )
+    );
+
+    let clock = warp_seconds(&mut context, TEST_INACTIVITY_PERIOD).await;
+    claim_inactive_as(&mut context, &pda, &beneficiary)
+        .await
+        .unwrap();
+
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.owner, beneficiary.pubkey());
+    assert_eq!(record.inheritance, None);
+    assert_eq!(record.last_activity, clock.unix_timestamp);
+}
+
+#[tokio::test]
+async fn owner_activity_restarts_inactivity_period() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let beneficiary = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::SelfCustody,
+        10_000_000,
+    )
+    .await;
+    set_beneficiary_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        Some(Inheritance {
+            beneficiary: beneficiary.pubkey(),
+            inactivity_period: TEST_INACTIVITY_PERIOD,
+        }),
+        AuthorityMode::SelfCustody,
+    )
+    .await
+    .unwrap();
+    let set_at = get_record(&mut context, pda).await.last_activity;
+
+    // The owner withdraws partway through the inactivity period.
+    let clock = warp_seconds(&mut context, TEST_INACTIVITY_PERIOD / 2).await;
+    withdraw_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        1_000_000,
+        AuthorityMode::SelfCustody,
+    )
+    .await
+    .unwrap();
+    let record = get_record(&mut context, pda).await;
+    assert_eq!(record.last_activity, clock.unix_timestamp);
+    assert!(record.last_activity > set_at);
+
+    // The period since the beneficiary was set is over, but not since the withdrawal.
+    let clock = warp_seconds(&mut context, TEST_INACTIVITY_PERIOD * 3 / 4).await;
+    assert!(clock.unix_timestamp >= set_at + TEST_INACTIVITY_PERIOD);
+    assert!(clock.unix_timestamp < record.last_activity + TEST_INACTIVITY_PERIOD);
+    assert_eq!(
+        claim_inactive_as(&mut context, &pda, &beneficiary)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::OwnerNotInactive as u32)
+        )
+    );
+    assert_eq!(get_record(&mut context, pda).await.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn claim_inactive_fail_wrong_beneficiary() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    assert_eq!(
+        claim_inactive_as(&mut context, &pda, &Keypair::new())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::NoBeneficiary as u32)
+        )
+    );
+
+    set_beneficiary_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        Some(Inheritance {
+            beneficiary: Pubkey::new_unique(),
+            inactivity_period: TEST_INACTIVITY_PERIOD,
+        }),
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    warp_seconds(&mut context, TEST_INACTIVITY_PERIOD).await;
+    assert_eq!(
+        claim_inactive_as(&mut context, &pda, &Keypair::new())
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::IncorrectAccountKey as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_beneficiary_fail_invalid_period() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    assert_eq!(
+        set_beneficiary_as(
+            &mut context,
+            &pda,
+            &dart,
+            &owner,
+            Some(Inheritance {
+                beneficiary: Pubkey::new_unique(),
+                inactivity_period: 0,
+            }),
+            AuthorityMode::Custodial,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidInactivityPeriod as u32)
+        )
+    );
+}
+
+// Helper: attach metadata to a vault, signed per its authority mode.
+async fn set_metadata_as(
+    context: &mut ProgramTestContext,
+    pda: &Pubkey,
+    dart: &Keypair,
+    owner: &Keypair,
+    metadata: VaultMetadata,
+    mode: AuthorityMode,
+) -> Result<(), BanksClientError> {
+    let mut signers = vec![&context.payer];
+    if mode.dart_signs() {
+        signers.push(dart);
+    }
+    if mode.owner_signs() {
+        signers.push(owner);
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::set_metadata(
+            &context.payer.pubkey(),
+            pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            metadata,
+            mode,
+        )],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+// Helper: get the metadata attached to a vault, if any.
+async fn get_metadata(context: &mut ProgramTestContext, pda: &Pubkey) -> Option<Metadata> {
+    let (address, _) = find_metadata_address(pda);
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .map(|account| Metadata::unpack(&account.data).unwrap())
+}
+
+#[tokio::test]
+async fn set_and_clear_metadata_success() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let lamports = context.banks_client.get_balance(pda).await.unwrap();
+
+    let metadata = VaultMetadata {
+        reference: "cust-0042".to_string(),
+        label: "College fund".to_string(),
+        category: "savings".to_string(),
+    };
+    set_metadata_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        metadata.clone(),
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+
+    let attached = get_metadata(&mut context, &pda).await.unwrap();
+    assert_eq!(attached.vault, pda);
+    assert_eq!(attached.metadata, metadata);
+
+    // The vault account itself is left untouched.
+    let account = context
+        .banks_client
+        .get_account(pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), VaultRecord::LEN);
+    assert_eq!(account.lamports, lamports);
+
+    // Shorter metadata replaces the previous metadata in full.
+    let shorter = VaultMetadata {
+        label: "Fund".to_string(),
+        ..VaultMetadata::default()
+    };
+    set_metadata_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        shorter.clone(),
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_metadata(&mut context, &pda).await.unwrap().metadata,
+        shorter
+    );
+
+    // Clearing closes the metadata account, releasing its rent.
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &receiver,
+                rent.minimum_balance(0),
+            ),
+            instruction::clear_metadata(
+                &pda,
+                &dart.pubkey(),
+                &owner.pubkey(),
+                &receiver,
+                AuthorityMode::Custodial,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_eq!(get_metadata(&mut context, &pda).await, None);
+    assert_eq!(
+        context.banks_client.get_balance(receiver).await.unwrap(),
+        rent.minimum_balance(0) + rent.minimum_balance(Metadata::LEN)
+    );
+    assert_eq!(
+        context.banks_client.get_balance(pda).await.unwrap(),
+        lamports
+    );
+}
+
+#[tokio::test]
+async fn set_metadata_fail_too_large() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    let metadata = VaultMetadata {
+        reference: "x".repeat(VaultMetadata::MAX_LEN),
+        ..VaultMetadata::default()
+    };
+    assert_eq!(
+        set_metadata_as(
+            &mut context,
+            &pda,
+            &dart,
+            &owner,
+            metadata,
+            AuthorityMode::Custodial,
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::MetadataTooLarge as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_closes_metadata() {
+    let mut context = program_test().start_with_context().await;
+
+    let dart = Keypair::new();
+    let owner = Keypair::new();
+    initialize_config(&mut context, &[dart.pubkey()]).await;
+
+    let pda = create_funded_vault(
+        &mut context,
+        &dart,
+        &owner,
+        AuthorityMode::Custodial,
+        10_000_000,
+    )
+    .await;
+    set_metadata_as(
+        &mut context,
+        &pda,
+        &dart,
+        &owner,
+        VaultMetadata {
+            reference: "cust-0042".to_string(),
+            ..VaultMetadata::default()
+        },
+        AuthorityMode::Custodial,
+    )
+    .await
+    .unwrap();
+    // The metadata rent is paid out with the vault balance.
+    let lamports = context.banks_client.get_balance(pda).await.unwrap()
+        - Rent::default().minimum_balance(VaultRecord::LEN)
+        + context
+            .banks_client
+            .get_balance(find_metadata_address(&pda).0)
+            .await
+            .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction::close_account(
+            &pda,
+            &dart.pubkey(),
+            &owner.pubkey(),
+            &context.payer.pubkey(),
+            AuthorityMode::Custodial,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &dart],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(get_metadata(&mut context, &pda).await, None);
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(owner.pubkey())
+            .await
+            .unwrap(),
+        lamports
     );
 }
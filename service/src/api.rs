@@ -1,11 +1,31 @@
+use crate::proto;
 use crate::proto::fauxstodian_service_server::FauxstodianService;
 use crate::proto::{
-    CloseAccountRequest, CloseAccountResponse, CreateAccountRequest, CreateAccountResponse,
-    GetBalanceRequest, GetBalanceResponse, TransferOwnershipRequest, TransferOwnershipResponse,
+    AcceptOwnerRequest, AcceptOwnerResponse, ApproveDelegateRequest, ApproveDelegateResponse,
+    CancelOwnerProposalRequest, CancelOwnerProposalResponse, CancelRecoveryRequest,
+    CancelRecoveryResponse, ClearMetadataRequest, ClearMetadataResponse, CloseAccountRequest,
+    CloseAccountResponse, CompleteRecoveryRequest, CompleteRecoveryResponse, CreateAccountRequest,
+    CreateAccountResponse, FreezeVaultRequest, FreezeVaultResponse, GetBalanceRequest,
+    GetBalanceResponse, GetBeneficiaryRequest, GetBeneficiaryResponse, GetLimitsRequest,
+    GetLimitsResponse, GetOwnersRequest, GetOwnersResponse, GetRecoveryRequest,
+    GetRecoveryResponse, GetTokenBalancesRequest, GetTokenBalancesResponse, ListDelegatesRequest,
+    ListDelegatesResponse, ListStakesRequest, ListStakesResponse, ProposeOwnerRequest,
+    ProposeOwnerResponse, RevokeDelegateRequest, RevokeDelegateResponse, RotateIntermediaryRequest,
+    RotateIntermediaryResponse, SetBeneficiaryRequest, SetBeneficiaryResponse, SetGuardianRequest,
+    SetGuardianResponse, SetLimitsRequest, SetLimitsResponse, SetMetadataRequest,
+    SetMetadataResponse, SetOwnersRequest, SetOwnersResponse, StakeRequest, StakeResponse,
+    StartRecoveryRequest, StartRecoveryResponse, ThawVaultRequest, ThawVaultResponse,
+    TransferOwnershipRequest, TransferOwnershipResponse, TransferRequest, TransferResponse,
+    TransferShareRequest, TransferShareResponse, UnstakeRequest, UnstakeResponse, WithdrawRequest,
+    WithdrawResponse, WithdrawTokenRequest, WithdrawTokenResponse,
 };
 use crate::service::{Error, Service};
 use log::info;
+use std::pin::Pin;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 use tonic::{Request, Response, Status};
+use vault::state::{AuthorityMode, UnlockAt, VaultMetadata, WithdrawalLimit};
 
 /// Define the fauxstodian API type.
 pub struct FauxstodianApi {
@@ -24,13 +44,67 @@ impl From<Error> for Status {
     fn from(err: Error) -> Self {
         match err {
             Error::InvalidArgument { message } => Status::invalid_argument(message),
+            Error::FailedPrecondition { message } => Status::failed_precondition(message),
+            Error::PermissionDenied { message } => Status::permission_denied(message),
+            Error::NotFound { message } => Status::not_found(message),
             Error::InternalError { message } => Status::internal(message),
         }
     }
 }
 
+/// Map grpc authority modes to vault authority modes.
+impl From<proto::AuthorityMode> for AuthorityMode {
+    fn from(mode: proto::AuthorityMode) -> Self {
+        match mode {
+            proto::AuthorityMode::Unspecified | proto::AuthorityMode::Custodial => {
+                AuthorityMode::Custodial
+            }
+            proto::AuthorityMode::SelfCustody => AuthorityMode::SelfCustody,
+            proto::AuthorityMode::CoSign => AuthorityMode::CoSign,
+        }
+    }
+}
+
+/// Map grpc lock-up times to vault time locks.
+impl From<proto::create_account_request::UnlockAt> for UnlockAt {
+    fn from(unlock_at: proto::create_account_request::UnlockAt) -> Self {
+        match unlock_at {
+            proto::create_account_request::UnlockAt::UnlockTimestamp(timestamp) => {
+                UnlockAt::Timestamp(timestamp)
+            }
+            proto::create_account_request::UnlockAt::UnlockSlot(slot) => UnlockAt::Slot(slot),
+        }
+    }
+}
+
+/// Map grpc withdrawal limits to vault withdrawal limits.
+impl From<proto::WithdrawalLimit> for WithdrawalLimit {
+    fn from(limit: proto::WithdrawalLimit) -> Self {
+        WithdrawalLimit {
+            amount: limit.lamports,
+            window: limit.window_seconds,
+        }
+    }
+}
+
+/// Map vault withdrawal limits to grpc withdrawal limits.
+impl From<WithdrawalLimit> for proto::WithdrawalLimit {
+    fn from(limit: WithdrawalLimit) -> Self {
+        proto::WithdrawalLimit {
+            lamports: limit.amount,
+            window_seconds: limit.window,
+        }
+    }
+}
+
+/// Stream of bulk rotation progress.
+type RotationStream =
+    Pin<Box<dyn Stream<Item = Result<RotateIntermediaryResponse, Status>> + Send>>;
+
 #[tonic::async_trait]
 impl FauxstodianService for FauxstodianApi {
+    type RotateIntermediaryStream = RotationStream;
+
     /// Create a new account backed by a solana vault.
     async fn create_account(
         &self,
@@ -38,7 +112,27 @@ impl FauxstodianService for FauxstodianApi {
     ) -> Result<Response<CreateAccountResponse>, Status> {
         info!("Create account request from {:?}", request.remote_addr());
         let reqr = request.get_ref();
-        match self.service.create_account(&reqr.seed, &reqr.owner).await {
+        let mode = reqr.authority_mode().into();
+        let unlock_at = reqr.unlock_at.clone().map(UnlockAt::from);
+        let (owner, co_owners, threshold) = match &reqr.owners {
+            Some(proto::create_account_request::Owners::Owner(owner)) => {
+                (owner.as_str(), vec![], 0)
+            }
+            Some(proto::create_account_request::Owners::CoOwners(co_ownership)) => (
+                "",
+                co_ownership
+                    .co_owners
+                    .iter()
+                    .map(|c| (c.owner.clone(), c.shares))
+                    .collect(),
+                co_ownership.threshold,
+            ),
+            None => ("", vec![], 0),
+        };
+        let future = self
+            .service
+            .create_account(&reqr.seed, owner, mode, unlock_at, &co_owners, threshold);
+        match future.await {
             Ok(account) => {
                 let signature = account.signature_hash();
                 info!("Created account; signature = {signature}");
@@ -60,6 +154,12 @@ impl FauxstodianService for FauxstodianApi {
             Ok(balance) => Ok(Response::new(GetBalanceResponse {
                 pub_key: balance.pda,
                 lamports: balance.lamports,
+                frozen: balance.frozen,
+                metadata: balance.metadata.map(|m| proto::VaultMetadata {
+                    reference: m.reference,
+                    label: m.label,
+                    category: m.category,
+                }),
             })),
             Err(err) => Err(err.into()),
         }
@@ -89,6 +189,67 @@ impl FauxstodianService for FauxstodianApi {
         }
     }
 
+    /// Propose a new owner for a solana vault.
+    async fn propose_owner(
+        &self,
+        request: Request<ProposeOwnerRequest>,
+    ) -> Result<Response<ProposeOwnerResponse>, Status> {
+        info!("Propose owner request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let future = self
+            .service
+            .propose_owner(&reqr.pda, &reqr.owner, &reqr.new_owner);
+        match future.await {
+            Ok(signature) => {
+                info!("Owner proposed; signature = {:?}", signature.hash);
+                Ok(Response::new(ProposeOwnerResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Prepare a transaction for the proposed owner of a solana vault to sign.
+    async fn accept_owner(
+        &self,
+        request: Request<AcceptOwnerRequest>,
+    ) -> Result<Response<AcceptOwnerResponse>, Status> {
+        info!("Accept owner request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self.service.accept_owner(&reqr.pda, &reqr.new_owner).await {
+            Ok(pending) => Ok(Response::new(AcceptOwnerResponse {
+                transaction: pending.encoded,
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Cancel a pending ownership proposal for a solana vault.
+    async fn cancel_owner_proposal(
+        &self,
+        request: Request<CancelOwnerProposalRequest>,
+    ) -> Result<Response<CancelOwnerProposalResponse>, Status> {
+        info!(
+            "Cancel owner proposal request from {:?}",
+            request.remote_addr()
+        );
+        let reqr = request.get_ref();
+        match self
+            .service
+            .cancel_owner_proposal(&reqr.pda, &reqr.owner)
+            .await
+        {
+            Ok(signature) => {
+                info!("Owner proposal cancelled; signature = {:?}", signature.hash);
+                Ok(Response::new(CancelOwnerProposalResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// Close an account, withdrawing all lamports to the vault owner.
     async fn close_account(
         &self,
@@ -96,14 +257,617 @@ impl FauxstodianService for FauxstodianApi {
     ) -> Result<Response<CloseAccountResponse>, Status> {
         info!("Close account request from {:?}", request.remote_addr());
         let reqr = request.get_ref();
-        match self.service.close_account(&reqr.pda, &reqr.owner).await {
-            Ok(signature) => {
-                info!("Account closed; signature = {:?}", signature.hash);
+        let approval = reqr
+            .approval
+            .as_ref()
+            .map(|a| (a.nonce, a.signature.as_slice()));
+        match self
+            .service
+            .close_account(&reqr.pda, &reqr.owner, approval)
+            .await
+        {
+            Ok(payout) => {
+                info!("Account closed; signature = {:?}", payout.signature.hash);
                 Ok(Response::new(CloseAccountResponse {
+                    signature: payout.signature.hash,
+                    fee: payout.fee,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Withdraw part of an account balance to the vault owner.
+    async fn withdraw(
+        &self,
+        request: Request<WithdrawRequest>,
+    ) -> Result<Response<WithdrawResponse>, Status> {
+        info!("Withdraw request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let approval = reqr
+            .approval
+            .as_ref()
+            .map(|a| (a.nonce, a.signature.as_slice()));
+        let future = self
+            .service
+            .withdraw(&reqr.pda, &reqr.owner, reqr.lamports, approval);
+        match future.await {
+            Ok(payout) => {
+                info!("Withdraw success; signature = {:?}", payout.signature.hash);
+                Ok(Response::new(WithdrawResponse {
+                    signature: payout.signature.hash,
+                    fee: payout.fee,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Freeze a solana vault for a compliance hold.
+    async fn freeze_vault(
+        &self,
+        request: Request<FreezeVaultRequest>,
+    ) -> Result<Response<FreezeVaultResponse>, Status> {
+        info!("Freeze vault request from {:?}", request.remote_addr());
+        match self.service.freeze_vault(&request.get_ref().pda).await {
+            Ok(signature) => {
+                info!("Vault frozen; signature = {:?}", signature.hash);
+                Ok(Response::new(FreezeVaultResponse {
                     signature: signature.hash,
                 }))
             }
             Err(err) => Err(err.into()),
         }
     }
+
+    /// Lift a compliance hold from a solana vault.
+    async fn thaw_vault(
+        &self,
+        request: Request<ThawVaultRequest>,
+    ) -> Result<Response<ThawVaultResponse>, Status> {
+        info!("Thaw vault request from {:?}", request.remote_addr());
+        match self.service.thaw_vault(&request.get_ref().pda).await {
+            Ok(signature) => {
+                info!("Vault thawed; signature = {:?}", signature.hash);
+                Ok(Response::new(ThawVaultResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Approve a delegate to withdraw from a solana vault.
+    async fn approve_delegate(
+        &self,
+        request: Request<ApproveDelegateRequest>,
+    ) -> Result<Response<ApproveDelegateResponse>, Status> {
+        info!("Approve delegate request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let future = self.service.approve_delegate(
+            &reqr.pda,
+            &reqr.owner,
+            &reqr.delegate,
+            reqr.lamports,
+            reqr.expires_at,
+            &reqr.recipient,
+        );
+        match future.await {
+            Ok(signature) => {
+                info!("Delegate approved; signature = {:?}", signature.hash);
+                Ok(Response::new(ApproveDelegateResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Revoke a delegate's allowance on a solana vault.
+    async fn revoke_delegate(
+        &self,
+        request: Request<RevokeDelegateRequest>,
+    ) -> Result<Response<RevokeDelegateResponse>, Status> {
+        info!("Revoke delegate request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let future = self
+            .service
+            .revoke_delegate(&reqr.pda, &reqr.owner, &reqr.delegate);
+        match future.await {
+            Ok(signature) => {
+                info!("Delegate revoked; signature = {:?}", signature.hash);
+                Ok(Response::new(RevokeDelegateResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// List the delegates of a solana vault.
+    async fn list_delegates(
+        &self,
+        request: Request<ListDelegatesRequest>,
+    ) -> Result<Response<ListDelegatesResponse>, Status> {
+        info!("List delegates request from {:?}", request.remote_addr());
+        match self.service.list_delegates(&request.get_ref().pda).await {
+            Ok(delegates) => Ok(Response::new(ListDelegatesResponse {
+                delegates: delegates
+                    .into_iter()
+                    .map(|delegate| proto::Delegate {
+                        delegate: delegate.delegate,
+                        lamports: delegate.lamports,
+                        expires_at: delegate.expires_at,
+                        recipient: delegate.recipient,
+                    })
+                    .collect(),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the withdrawal limit of a solana vault.
+    async fn get_limits(
+        &self,
+        request: Request<GetLimitsRequest>,
+    ) -> Result<Response<GetLimitsResponse>, Status> {
+        info!("Get limits request from {:?}", request.remote_addr());
+        match self.service.get_limits(&request.get_ref().pda).await {
+            Ok(limits) => Ok(Response::new(GetLimitsResponse {
+                limit: limits.limit.map(Into::into),
+                window_start: limits.window_start,
+                withdrawn: limits.withdrawn,
+                pending: limits.pending.is_some(),
+                pending_limit: limits.pending.and_then(|p| p.limit).map(Into::into),
+                pending_effective_at: limits.pending.map_or(0, |p| p.effective_at),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Set the withdrawal limit of a solana vault.
+    async fn set_limits(
+        &self,
+        request: Request<SetLimitsRequest>,
+    ) -> Result<Response<SetLimitsResponse>, Status> {
+        info!("Set limits request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let limit = reqr.limit.clone().map(WithdrawalLimit::from);
+        match self.service.set_limits(&reqr.pda, limit).await {
+            Ok(signature) => {
+                info!("Limits set; signature = {:?}", signature.hash);
+                Ok(Response::new(SetLimitsResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Move lamports between two solana vaults.
+    async fn transfer(
+        &self,
+        request: Request<TransferRequest>,
+    ) -> Result<Response<TransferResponse>, Status> {
+        info!("Transfer request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .transfer(&reqr.pda, &reqr.owner, &reqr.destination, reqr.lamports)
+            .await
+        {
+            Ok(signature) => {
+                info!("Transferred; signature = {:?}", signature.hash);
+                Ok(Response::new(TransferResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Set the guardian of a solana vault.
+    async fn set_guardian(
+        &self,
+        request: Request<SetGuardianRequest>,
+    ) -> Result<Response<SetGuardianResponse>, Status> {
+        info!("Set guardian request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .set_guardian(&reqr.pda, &reqr.owner, reqr.guardian.as_deref())
+            .await
+        {
+            Ok(signature) => {
+                info!("Guardian set; signature = {:?}", signature.hash);
+                Ok(Response::new(SetGuardianResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Build a transaction starting the recovery of a solana vault.
+    async fn start_recovery(
+        &self,
+        request: Request<StartRecoveryRequest>,
+    ) -> Result<Response<StartRecoveryResponse>, Status> {
+        info!("Start recovery request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .start_recovery(&reqr.pda, &reqr.new_owner, &reqr.signers)
+            .await
+        {
+            Ok(pending) => Ok(Response::new(StartRecoveryResponse {
+                transaction: pending.encoded,
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Cancel a pending recovery of a solana vault.
+    async fn cancel_recovery(
+        &self,
+        request: Request<CancelRecoveryRequest>,
+    ) -> Result<Response<CancelRecoveryResponse>, Status> {
+        info!("Cancel recovery request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self.service.cancel_recovery(&reqr.pda, &reqr.owner).await {
+            Ok(signature) => {
+                info!("Recovery cancelled; signature = {:?}", signature.hash);
+                Ok(Response::new(CancelRecoveryResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Complete the recovery of a solana vault.
+    async fn complete_recovery(
+        &self,
+        request: Request<CompleteRecoveryRequest>,
+    ) -> Result<Response<CompleteRecoveryResponse>, Status> {
+        info!("Complete recovery request from {:?}", request.remote_addr());
+        match self.service.complete_recovery(&request.get_ref().pda).await {
+            Ok(signature) => {
+                info!("Recovery completed; signature = {:?}", signature.hash);
+                Ok(Response::new(CompleteRecoveryResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the guardian and pending recovery of a solana vault.
+    async fn get_recovery(
+        &self,
+        request: Request<GetRecoveryRequest>,
+    ) -> Result<Response<GetRecoveryResponse>, Status> {
+        info!("Get recovery request from {:?}", request.remote_addr());
+        match self.service.get_recovery(&request.get_ref().pda).await {
+            Ok(status) => Ok(Response::new(GetRecoveryResponse {
+                guardian: status.guardian,
+                pending: status.recovery.is_some(),
+                new_owner: status
+                    .recovery
+                    .map(|r| r.new_owner.to_string())
+                    .unwrap_or_default(),
+                completes_at: status.recovery.map_or(0, |r| r.completes_at),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the token balances of a solana vault.
+    async fn get_token_balances(
+        &self,
+        request: Request<GetTokenBalancesRequest>,
+    ) -> Result<Response<GetTokenBalancesResponse>, Status> {
+        info!(
+            "Get token balances request from {:?}",
+            request.remote_addr()
+        );
+        match self
+            .service
+            .get_token_balances(&request.get_ref().pda)
+            .await
+        {
+            Ok(balances) => Ok(Response::new(GetTokenBalancesResponse {
+                balances: balances
+                    .into_iter()
+                    .map(|b| proto::TokenBalance {
+                        mint: b.mint,
+                        token_program: b.token_program,
+                        amount: b.amount,
+                        decimals: b.decimals.into(),
+                    })
+                    .collect(),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Withdraw tokens from a solana vault to the owner.
+    async fn withdraw_token(
+        &self,
+        request: Request<WithdrawTokenRequest>,
+    ) -> Result<Response<WithdrawTokenResponse>, Status> {
+        info!("Withdraw token request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .withdraw_token(&reqr.pda, &reqr.owner, &reqr.mint, reqr.amount)
+            .await
+        {
+            Ok(signature) => {
+                info!("Token withdraw success; signature = {:?}", signature.hash);
+                Ok(Response::new(WithdrawTokenResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Stake lamports from a solana vault with a validator.
+    async fn stake(
+        &self,
+        request: Request<StakeRequest>,
+    ) -> Result<Response<StakeResponse>, Status> {
+        info!("Stake request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .stake(&reqr.pda, &reqr.owner, &reqr.vote_account, reqr.lamports)
+            .await
+        {
+            Ok((signature, stake_account)) => {
+                info!("Stake success; signature = {:?}", signature.hash);
+                Ok(Response::new(StakeResponse {
+                    signature: signature.hash,
+                    stake_account,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Deactivate or withdraw a stake position of a solana vault.
+    async fn unstake(
+        &self,
+        request: Request<UnstakeRequest>,
+    ) -> Result<Response<UnstakeResponse>, Status> {
+        info!("Unstake request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self
+            .service
+            .unstake(&reqr.pda, &reqr.owner, &reqr.stake_account)
+            .await
+        {
+            Ok((signature, withdrawn)) => {
+                info!("Unstake success; signature = {:?}", signature.hash);
+                Ok(Response::new(UnstakeResponse {
+                    signature: signature.hash,
+                    withdrawn,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// List the stake positions of a solana vault.
+    async fn list_stakes(
+        &self,
+        request: Request<ListStakesRequest>,
+    ) -> Result<Response<ListStakesResponse>, Status> {
+        info!("List stakes request from {:?}", request.remote_addr());
+        match self.service.list_stakes(&request.get_ref().pda).await {
+            Ok(stakes) => Ok(Response::new(ListStakesResponse {
+                stakes: stakes
+                    .into_iter()
+                    .map(|s| proto::StakePosition {
+                        stake_account: s.stake_account,
+                        lamports: s.lamports,
+                        voter: s.voter,
+                        activation_epoch: s.activation_epoch,
+                        deactivation_epoch: s.deactivation_epoch,
+                    })
+                    .collect(),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Make a solana vault co-owned, or replace its co-owners.
+    async fn set_owners(
+        &self,
+        request: Request<SetOwnersRequest>,
+    ) -> Result<Response<SetOwnersResponse>, Status> {
+        info!("Set owners request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let co_owners: Vec<(String, u32)> = reqr
+            .co_owners
+            .iter()
+            .map(|c| (c.owner.clone(), c.shares))
+            .collect();
+        let future = self
+            .service
+            .set_owners(&reqr.pda, &reqr.owner, &co_owners, reqr.threshold);
+        match future.await {
+            Ok(signature) => {
+                info!("Owners set; signature = {:?}", signature.hash);
+                Ok(Response::new(SetOwnersResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the owners of a solana vault with their shares.
+    async fn get_owners(
+        &self,
+        request: Request<GetOwnersRequest>,
+    ) -> Result<Response<GetOwnersResponse>, Status> {
+        info!("Get owners request from {:?}", request.remote_addr());
+        match self.service.get_owners(&request.get_ref().pda).await {
+            Ok(owners) => Ok(Response::new(GetOwnersResponse {
+                owners: owners
+                    .owners
+                    .into_iter()
+                    .map(|c| proto::CoOwner {
+                        owner: c.owner.to_string(),
+                        shares: c.shares,
+                    })
+                    .collect(),
+                threshold: owners.threshold.into(),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Transfer a co-owner's share of a solana vault.
+    async fn transfer_share(
+        &self,
+        request: Request<TransferShareRequest>,
+    ) -> Result<Response<TransferShareResponse>, Status> {
+        info!("Transfer share request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let future = self
+            .service
+            .transfer_share(&reqr.pda, &reqr.co_owner, &reqr.new_co_owner);
+        match future.await {
+            Ok(signature) => {
+                info!("Transfer share success; signature = {:?}", signature.hash);
+                Ok(Response::new(TransferShareResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Set the beneficiary of a solana vault.
+    async fn set_beneficiary(
+        &self,
+        request: Request<SetBeneficiaryRequest>,
+    ) -> Result<Response<SetBeneficiaryResponse>, Status> {
+        info!("Set beneficiary request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        let future = self.service.set_beneficiary(
+            &reqr.pda,
+            &reqr.owner,
+            reqr.beneficiary.as_deref(),
+            reqr.inactivity_period,
+        );
+        match future.await {
+            Ok(signature) => {
+                info!("Beneficiary set; signature = {:?}", signature.hash);
+                Ok(Response::new(SetBeneficiaryResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the beneficiary of a solana vault and the owner's last activity.
+    async fn get_beneficiary(
+        &self,
+        request: Request<GetBeneficiaryRequest>,
+    ) -> Result<Response<GetBeneficiaryResponse>, Status> {
+        info!("Get beneficiary request from {:?}", request.remote_addr());
+        match self.service.get_beneficiary(&request.get_ref().pda).await {
+            Ok(status) => Ok(Response::new(GetBeneficiaryResponse {
+                beneficiary: status.inheritance.map(|i| i.beneficiary.to_string()),
+                inactivity_period: status.inheritance.map_or(0, |i| i.inactivity_period),
+                last_activity: status.last_activity,
+                claimable_at: status.inheritance.map_or(0, |i| {
+                    status.last_activity.saturating_add(i.inactivity_period)
+                }),
+            })),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Attach metadata to a solana vault.
+    async fn set_metadata(
+        &self,
+        request: Request<SetMetadataRequest>,
+    ) -> Result<Response<SetMetadataResponse>, Status> {
+        info!("Set metadata request from {:?}", request.remote_addr());
+        let reqr = request.into_inner();
+        let metadata = reqr.metadata.unwrap_or_default();
+        let metadata = VaultMetadata {
+            reference: metadata.reference,
+            label: metadata.label,
+            category: metadata.category,
+        };
+        match self
+            .service
+            .set_metadata(&reqr.pda, &reqr.owner, metadata)
+            .await
+        {
+            Ok(signature) => {
+                info!("Metadata set; signature = {:?}", signature.hash);
+                Ok(Response::new(SetMetadataResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove the metadata of a solana vault.
+    async fn clear_metadata(
+        &self,
+        request: Request<ClearMetadataRequest>,
+    ) -> Result<Response<ClearMetadataResponse>, Status> {
+        info!("Clear metadata request from {:?}", request.remote_addr());
+        let reqr = request.get_ref();
+        match self.service.clear_metadata(&reqr.pda, &reqr.owner).await {
+            Ok(signature) => {
+                info!("Metadata cleared; signature = {:?}", signature.hash);
+                Ok(Response::new(ClearMetadataResponse {
+                    signature: signature.hash,
+                }))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Move every vault to a new intermediary, streaming progress per batch.
+    #[allow(clippy::result_large_err)] // Stream items carry a grpc status
+    async fn rotate_intermediary(
+        &self,
+        request: Request<RotateIntermediaryRequest>,
+    ) -> Result<Response<Self::RotateIntermediaryStream>, Status> {
+        info!(
+            "Rotate intermediary request from {:?}",
+            request.remote_addr()
+        );
+        let new_dart = request.into_inner().new_dart;
+        let (sender, receiver) = mpsc::channel(16);
+        let service = self.service.clone();
+        tokio::spawn(async move { service.rotate_intermediary(&new_dart, sender).await });
+        let stream = ReceiverStream::new(receiver).map(|result| match result {
+            Ok(progress) => {
+                info!(
+                    "Rotated {} of {} vaults; signature = {:?}",
+                    progress.rotated, progress.total, progress.signature.hash
+                );
+                Ok(RotateIntermediaryResponse {
+                    signature: progress.signature.hash,
+                    rotated: progress.rotated as u64,
+                    total: progress.total as u64,
+                })
+            }
+            Err(err) => Err(err.into()),
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
 }
@@ -1,20 +1,31 @@
-use solana_sdk::{signature::Keypair, signer::EncodableKey};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::EncodableKey};
 use std::env;
 use std::net::SocketAddr;
+use std::str::FromStr;
 
 /// Fauxstodian configuration.
 pub struct Config {
     pub rpc_url: String,
     pub keypair: Keypair,
+    pub multisig: Option<Pubkey>,
+    pub cosigner_keypairs: Vec<Keypair>,
     pub listen_addr: SocketAddr,
 }
 
 impl Config {
     /// Create a new config.
-    pub fn new(rpc_url: String, keypair: Keypair, listen_addr: SocketAddr) -> Self {
+    pub fn new(
+        rpc_url: String,
+        keypair: Keypair,
+        multisig: Option<Pubkey>,
+        cosigner_keypairs: Vec<Keypair>,
+        listen_addr: SocketAddr,
+    ) -> Self {
         Self {
             rpc_url,
             keypair,
+            multisig,
+            cosigner_keypairs,
             listen_addr,
         }
     }
@@ -30,6 +41,25 @@ impl Config {
         Keypair::read_from_file(keypair_file).expect("Unable to read keypair file")
     }
 
+    /// Load the optional multisig address acting as custodian (DART)
+    fn load_multisig() -> Option<Pubkey> {
+        env::var("SOLANA_MULTISIG_ADDRESS")
+            .ok()
+            .map(|address| Pubkey::from_str(&address).expect("Unable to parse multisig address"))
+    }
+
+    /// Load multisig cosigner keypairs from a comma separated list of files
+    fn load_cosigner_keypair_files() -> Vec<Keypair> {
+        env::var("SOLANA_COSIGNER_KEYPAIR_FILES")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|file| !file.trim().is_empty())
+            .map(|file| {
+                Keypair::read_from_file(file.trim()).expect("Unable to read cosigner keypair file")
+            })
+            .collect()
+    }
+
     /// Load fauxstodian server gRPC listen address
     fn load_listen_addr() -> SocketAddr {
         let listen_addr = env::var("GRPC_LISTEN_ADDR").unwrap_or("0.0.0.0:50055".into());
@@ -43,6 +73,8 @@ impl Default for Config {
         Config::new(
             Config::load_rpc_url(),
             Config::load_keypair_file(),
+            Config::load_multisig(),
+            Config::load_cosigner_keypair_files(),
             Config::load_listen_addr(),
         )
     }
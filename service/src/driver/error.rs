@@ -1,18 +1,102 @@
+use num_traits::FromPrimitive;
+use solana_client::client_error::ClientError;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use vault::error::VaultError;
+
 /// Solana driver errors.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("error getting vault account: {0}")]
     GetVaultAccountError(String),
-    #[error("error creating public key from seed: {0}")]
-    PubkeyWithSeedError(String),
+    #[error("error decoding vault record: {0}")]
+    DecodeVaultRecordError(String),
     #[error("error getting latest blockhash: {0}")]
     GetLatestBlockhashError(String),
     #[error("error creating vault: {0}")]
     CreateVaultError(String),
     #[error("error closing vault: {0}")]
     CloseVaultError(String),
+    #[error("error withdrawing from vault: {0}")]
+    WithdrawVaultError(String),
     #[error("error changing vault owner: {0}")]
     ChangeVaultOwnerError(String),
+    #[error("error proposing vault owner: {0}")]
+    ProposeVaultOwnerError(String),
+    #[error("error cancelling vault owner proposal: {0}")]
+    CancelVaultOwnerError(String),
+    #[error("error freezing vault: {0}")]
+    FreezeVaultError(String),
+    #[error("error thawing vault: {0}")]
+    ThawVaultError(String),
+    #[error("error finding vaults: {0}")]
+    FindVaultsError(String),
+    #[error("error rotating vault intermediary: {0}")]
+    RotateIntermediaryError(String),
+    #[error("error approving vault delegate: {0}")]
+    ApproveDelegateError(String),
+    #[error("error revoking vault delegate: {0}")]
+    RevokeDelegateError(String),
+    #[error("error finding vault allowances: {0}")]
+    FindAllowancesError(String),
+    #[error("error setting vault withdrawal limit: {0}")]
+    SetWithdrawalLimitError(String),
+    #[error("error transferring between vaults: {0}")]
+    TransferVaultError(String),
+    #[error("error setting vault guardian: {0}")]
+    SetGuardianError(String),
+    #[error("error cancelling vault recovery: {0}")]
+    CancelRecoveryError(String),
+    #[error("error completing vault recovery: {0}")]
+    CompleteRecoveryError(String),
+    #[error("error finding vault token accounts: {0}")]
+    FindTokenAccountsError(String),
+    #[error("error getting token mint: {0}")]
+    GetTokenMintError(String),
+    #[error("error withdrawing vault tokens: {0}")]
+    WithdrawTokenError(String),
+    #[error("error setting vault co-owners: {0}")]
+    SetCoOwnersError(String),
+    #[error("error getting vault co-owners: {0}")]
+    GetCoOwnersError(String),
+    #[error("error transferring vault share: {0}")]
+    TransferShareError(String),
+    #[error("error finding vault stakes: {0}")]
+    FindStakesError(String),
+    #[error("error staking from vault: {0}")]
+    StakeError(String),
+    #[error("error deactivating vault stake: {0}")]
+    DeactivateStakeError(String),
+    #[error("error withdrawing vault stake: {0}")]
+    WithdrawStakeError(String),
+    #[error("error setting vault beneficiary: {0}")]
+    SetBeneficiaryError(String),
+    #[error("error setting vault metadata: {0}")]
+    SetMetadataError(String),
+    #[error("error clearing vault metadata: {0}")]
+    ClearMetadataError(String),
+    #[error("error getting vault metadata: {0}")]
+    GetMetadataError(String),
+    #[error("error getting program config: {0}")]
+    GetProgramConfigError(String),
+    #[error("error getting transaction: {0}")]
+    GetTransactionError(String),
     #[error("error checking solana rpc health: {0}")]
     HealthCheckError(String),
+    #[error("vault program error: {0}")]
+    ProgramError(VaultError),
+}
+
+impl Error {
+    /// Convert a failed transaction into a driver error, decoding vault program errors
+    /// and otherwise falling back to the given operation error.
+    pub(crate) fn from_transaction(err: ClientError, otherwise: fn(String) -> Error) -> Error {
+        if let Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) =
+            err.get_transaction_error()
+        {
+            if let Some(vault_error) = VaultError::from_u32(code) {
+                return Error::ProgramError(vault_error);
+            }
+        }
+        otherwise(err.kind.to_string())
+    }
 }
@@ -1,20 +1,95 @@
-use solana_sdk::{hash::Hash, rent::Rent};
-use vault::state::VaultRecord;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::warn;
+use solana_sdk::{borsh0_10::try_from_slice_unchecked, hash::Hash, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+use vault::{
+    events::{VaultEvent, LOG_DATA_PREFIX},
+    state::{find_config_address, ProgramConfig},
+};
 
 use super::{rpc::SolanaRpc, Error, Result};
 
 impl SolanaRpc {
-    /// Calculate vault record rent values
-    pub(crate) fn calculate_rent(&self) -> (u64, u64) {
-        let space = VaultRecord::LEN;
-        let lamports = Rent::default().minimum_balance(space);
-        (space as u64, lamports)
-    }
-
     /// Get the latest blockhash using the rpc client.
     pub(crate) fn get_latest_blockhash(&self) -> Result<Hash> {
         self.rpc_client_ref()
             .get_latest_blockhash()
             .map_err(|err| Error::GetLatestBlockhashError(err.kind.to_string()))
     }
+
+    /// Get the vault program config.
+    pub(crate) fn get_program_config(&self) -> Result<ProgramConfig> {
+        let (address, _) = find_config_address();
+        let account = self
+            .rpc_client_ref()
+            .get_account(&address)
+            .map_err(|err| Error::GetProgramConfigError(err.to_string()))?;
+        try_from_slice_unchecked::<ProgramConfig>(&account.data)
+            .map_err(|err| Error::GetProgramConfigError(err.to_string()))
+    }
+
+    /// Decode the vault events logged by a confirmed transaction.
+    pub(crate) fn get_vault_events(&self, signature: &Signature) -> Result<Vec<VaultEvent>> {
+        let confirmed = self
+            .rpc_client_ref()
+            .get_transaction(signature, UiTransactionEncoding::Base64)
+            .map_err(|err| Error::GetTransactionError(err.kind.to_string()))?;
+        let meta = confirmed
+            .transaction
+            .meta
+            .ok_or_else(|| Error::GetTransactionError("missing transaction meta".into()))?;
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+
+        Ok(vault_program_data(&logs.unwrap_or_default())
+            .into_iter()
+            .filter_map(|data| STANDARD.decode(data).ok())
+            .filter_map(|data| VaultEvent::unpack(&data))
+            .collect())
+    }
+
+    /// Get the fee taken by the vault payout in a confirmed transaction, if
+    /// the transaction can be read back. The payout is already confirmed, so a
+    /// failed lookup is logged rather than returned.
+    pub(crate) fn get_payout_fee(&self, signature: &Signature) -> Option<u64> {
+        self.get_vault_events(signature)
+            .and_then(|events| {
+                events
+                    .into_iter()
+                    .find_map(|event| match event {
+                        VaultEvent::Closed { fee, .. } | VaultEvent::Withdrawn { fee, .. } => {
+                            Some(fee)
+                        }
+                        _ => None,
+                    })
+                    .ok_or_else(|| Error::GetTransactionError("payout event not found".into()))
+            })
+            .map_err(|err| warn!("Payout fee lookup failed for {signature}: {err}"))
+            .ok()
+    }
+}
+
+/// Select the program data lines logged while the vault program itself is
+/// executing, following the invoke nesting of the transaction logs so data
+/// logged by other programs, or by programs the vault invokes, is skipped.
+fn vault_program_data(logs: &[String]) -> Vec<&str> {
+    let vault_id = vault::id().to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut data = Vec::new();
+    for log in logs {
+        if let Some(payload) = log.strip_prefix(LOG_DATA_PREFIX) {
+            if stack.last() == Some(&vault_id.as_str()) {
+                data.push(payload);
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(program), Some("invoke")) => stack.push(program),
+                (Some(_), Some("success" | "failed:")) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    data
 }
@@ -1,4 +1,13 @@
-use solana_sdk::{account::Account, pubkey::Pubkey, signature::Signature};
+use crate::entity::Approval;
+use solana_sdk::{
+    account::Account, clock::UnixTimestamp, pubkey::Pubkey, signature::Signature,
+    stake::state::StakeStateV2, transaction::Transaction,
+};
+use spl_token_2022::state::{Account as TokenAccount, Mint};
+use vault::state::{
+    Allowance, AuthorityMode, CoOwner, CoOwners, Inheritance, UnlockAt, VaultMetadata, VaultRecord,
+    WithdrawalLimit,
+};
 
 // Wire up mods
 mod error;
@@ -16,11 +25,20 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[async_trait::async_trait]
 pub trait SolanaDriver: Send + Sync {
     /// Create a new vault.
-    async fn create_vault(&self, seed: &str, owner: &Pubkey) -> Result<(Pubkey, Signature)>;
+    async fn create_vault(
+        &self,
+        seed: &str,
+        owner: &Pubkey,
+        mode: AuthorityMode,
+        unlock_at: Option<UnlockAt>,
+    ) -> Result<(Pubkey, Signature)>;
 
     /// Return the vault account.
     async fn get_vault_account(&self, pda: &Pubkey) -> Result<Account>;
 
+    /// Return the decoded vault record.
+    async fn get_vault_record(&self, pda: &Pubkey) -> Result<VaultRecord>;
+
     /// Transfer ownership of a vault.
     async fn change_vault_owner(
         &self,
@@ -29,8 +47,228 @@ pub trait SolanaDriver: Send + Sync {
         new_owner: &Pubkey,
     ) -> Result<Signature>;
 
-    /// Close an existing vault.
-    async fn close_vault(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature>;
+    /// Propose a new owner for a vault.
+    async fn propose_vault_owner(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        new_owner: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Build a DART signed transaction accepting a vault ownership proposal,
+    /// awaiting the new owner's signature.
+    async fn accept_vault_owner(&self, pda: &Pubkey, new_owner: &Pubkey) -> Result<Transaction>;
+
+    /// Cancel a pending vault ownership proposal.
+    async fn cancel_vault_owner_proposal(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature>;
+
+    /// Close an existing vault, returning the fee taken if it could be read
+    /// back from the confirmed transaction. An owner approval
+    /// stands in for the owner signature. The co-owners of a co-owned vault
+    /// are paid out to the given wallets.
+    async fn close_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        approval: Option<&Approval>,
+        wallets: &[Pubkey],
+    ) -> Result<(Signature, Option<u64>)>;
+
+    /// Withdraw lamports from an existing vault, returning the fee taken if it
+    /// could be read back from the confirmed transaction. An owner approval
+    /// stands in for the owner signature. The co-owners of a
+    /// co-owned vault are paid out to the given wallets.
+    async fn withdraw_from_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        lamports: u64,
+        approval: Option<&Approval>,
+        wallets: &[Pubkey],
+    ) -> Result<(Signature, Option<u64>)>;
+
+    /// Freeze a vault for a compliance hold.
+    async fn freeze_vault(&self, pda: &Pubkey) -> Result<Signature>;
+
+    /// Lift a compliance hold from a vault.
+    async fn thaw_vault(&self, pda: &Pubkey) -> Result<Signature>;
+
+    /// List the vaults of the configured DART.
+    async fn find_vaults(&self) -> Result<Vec<Pubkey>>;
+
+    /// Move a batch of vaults to a new intermediary in a single transaction.
+    async fn rotate_vault_intermediary(
+        &self,
+        pdas: &[Pubkey],
+        new_dart: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Approve a delegate to withdraw from a vault to a recipient, replacing
+    /// any existing allowance.
+    async fn approve_vault_delegate(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        delegate: &Pubkey,
+        lamports: u64,
+        expires_at: Option<UnixTimestamp>,
+        recipient: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Revoke a delegate's allowance on a vault.
+    async fn revoke_vault_delegate(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        delegate: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// List the allowances drawing from a vault.
+    async fn find_vault_allowances(&self, pda: &Pubkey) -> Result<Vec<Allowance>>;
+
+    /// Set the withdrawal limit of a vault, or lift it.
+    async fn set_vault_withdrawal_limit(
+        &self,
+        pda: &Pubkey,
+        limit: Option<WithdrawalLimit>,
+    ) -> Result<Signature>;
+
+    /// Move lamports from one vault to another vault of the same DART.
+    async fn transfer_between_vaults(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        destination: &Pubkey,
+        lamports: u64,
+    ) -> Result<Signature>;
+
+    /// Set the guardian of a vault, or remove it.
+    async fn set_vault_guardian(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        guardian: Option<Pubkey>,
+    ) -> Result<Signature>;
+
+    /// Build a DART signed transaction starting a vault recovery, awaiting the
+    /// guardian's signature, or those of the given multisig guardian signers.
+    async fn start_vault_recovery(
+        &self,
+        pda: &Pubkey,
+        guardian: &Pubkey,
+        new_owner: &Pubkey,
+        signers: &[Pubkey],
+    ) -> Result<Transaction>;
+
+    /// Cancel a pending vault recovery.
+    async fn cancel_vault_recovery(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature>;
+
+    /// Complete a vault recovery whose challenge period is over.
+    async fn complete_vault_recovery(&self, pda: &Pubkey) -> Result<Signature>;
+
+    /// Find the token accounts of a vault under SPL Token and Token-2022,
+    /// with the token program of each.
+    async fn find_vault_token_accounts(&self, pda: &Pubkey) -> Result<Vec<(Pubkey, TokenAccount)>>;
+
+    /// Get a token mint with its token program.
+    async fn get_token_mint(&self, mint: &Pubkey) -> Result<(Pubkey, Mint)>;
+
+    /// Withdraw tokens from a vault to the owner's associated token account,
+    /// creating it if needed.
+    async fn withdraw_vault_token(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Signature>;
+
+    /// Create a new custodial vault with the given seed, co-owned by the given
+    /// co-owners.
+    async fn create_co_owned_vault(
+        &self,
+        seed: &str,
+        co_owners: Vec<CoOwner>,
+        threshold: u8,
+        unlock_at: Option<UnlockAt>,
+    ) -> Result<(Pubkey, Signature)>;
+
+    /// Make a vault co-owned, or replace its co-owners.
+    async fn set_vault_co_owners(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        co_owners: Vec<CoOwner>,
+        threshold: u8,
+    ) -> Result<Signature>;
+
+    /// Get the co-owner list of a vault, if it is co-owned.
+    async fn get_vault_co_owners(&self, pda: &Pubkey) -> Result<Option<CoOwners>>;
+
+    /// Transfer a co-owner's share of a co-owned vault.
+    async fn transfer_vault_share(
+        &self,
+        pda: &Pubkey,
+        co_owner: &Pubkey,
+        new_co_owner: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Set the beneficiary of a vault, or remove it.
+    async fn set_vault_beneficiary(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        inheritance: Option<Inheritance>,
+    ) -> Result<Signature>;
+
+    /// Attach metadata to a vault, replacing any previous metadata.
+    async fn set_vault_metadata(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        metadata: VaultMetadata,
+    ) -> Result<Signature>;
+
+    /// Remove the metadata of a vault, returning its rent to the DART.
+    async fn clear_vault_metadata(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature>;
+
+    /// Get the metadata attached to a vault, if any.
+    async fn get_vault_metadata(&self, pda: &Pubkey) -> Result<Option<VaultMetadata>>;
+
+    /// Find the stake accounts a vault is the withdraw authority of, with
+    /// their balance.
+    async fn find_vault_stakes(&self, pda: &Pubkey) -> Result<Vec<(Pubkey, u64, StakeStateV2)>>;
+
+    /// Stake lamports from a vault in a new stake account at the given index,
+    /// delegated to a validator.
+    async fn stake_from_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        vote_account: &Pubkey,
+        index: u64,
+        lamports: u64,
+    ) -> Result<Signature>;
+
+    /// Deactivate a vault stake account.
+    async fn deactivate_vault_stake(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        stake_account: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Withdraw an inactive vault stake account back into the vault.
+    async fn withdraw_vault_stake(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        stake_account: &Pubkey,
+    ) -> Result<Signature>;
+
+    /// Whether the vault program is globally paused.
+    async fn is_program_paused(&self) -> Result<bool>;
 
     /// Check the solana connection.
     async fn health_check(&self) -> Result<()>;
@@ -1,9 +1,29 @@
-use solana_client::rpc_client::RpcClient;
+use crate::entity::Approval;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_sdk::{
-    account::Account, pubkey::Pubkey, signature::Keypair, signature::Signature, signer::Signer,
-    system_instruction, transaction::Transaction,
+    account::Account, clock::UnixTimestamp, instruction::Instruction, pubkey::Pubkey,
+    signature::Keypair, signature::Signature, signer::Signer, stake, stake::state::StakeStateV2,
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account as TokenAccount, Mint},
+};
+use vault::{
+    approval::{ApprovalAction, OwnerApproval},
+    instruction,
+    state::{
+        find_co_owners_address, find_metadata_address, find_stake_address, find_vault_address,
+        Allowance, AuthorityMode, CoOwner, CoOwners, Inheritance, Metadata, Multisig, UnlockAt,
+        VaultMetadata, VaultRecord, WithdrawalLimit,
+    },
 };
-use vault::{id, instruction};
 
 use super::{Error, Result, SolanaDriver};
 
@@ -11,15 +31,25 @@ use super::{Error, Result, SolanaDriver};
 pub struct SolanaRpc {
     rpc_client: RpcClient,
     signing_keys: Keypair,
+    multisig: Option<Pubkey>,
+    cosigning_keys: Vec<Keypair>,
 }
 
 impl SolanaRpc {
-    /// Create a new Solana JSON-RPC driver.
-    pub fn new<U: ToString>(url: U, signing_keys: Keypair) -> Self {
+    /// Create a new Solana JSON-RPC driver. When a multisig is given, it acts as the
+    /// DART, approved by the signing keys together with the cosigning keys.
+    pub fn new<U: ToString>(
+        url: U,
+        signing_keys: Keypair,
+        multisig: Option<Pubkey>,
+        cosigning_keys: Vec<Keypair>,
+    ) -> Self {
         let rpc_client = RpcClient::new(url);
         Self {
             rpc_client,
             signing_keys,
+            multisig,
+            cosigning_keys,
         }
     }
 
@@ -27,38 +57,89 @@ impl SolanaRpc {
     pub fn rpc_client_ref(&self) -> &RpcClient {
         &self.rpc_client
     }
+
+    /// The DART authority: the multisig if configured, else the signing keys.
+    fn authority(&self) -> Pubkey {
+        self.multisig.unwrap_or_else(|| self.signing_keys.pubkey())
+    }
+
+    /// Adapt an instruction requiring the DART signature to the configured authority.
+    fn authorize(&self, ix: Instruction) -> Instruction {
+        match self.multisig {
+            Some(multisig) => {
+                let signers: Vec<Pubkey> = self.signers().iter().map(|k| k.pubkey()).collect();
+                instruction::with_multisig_signers(ix, &multisig, &signers)
+            }
+            None => ix,
+        }
+    }
+
+    /// Adapt an instruction to an owner approval, if any, preceding it with the
+    /// ed25519 instruction verifying the approval.
+    fn with_approval(
+        &self,
+        ix: Instruction,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        approval: Option<&Approval>,
+        action: ApprovalAction,
+        amount: u64,
+    ) -> Vec<Instruction> {
+        match approval {
+            Some(approval) => {
+                let message = OwnerApproval {
+                    program_id: vault::id(),
+                    vault: *pda,
+                    action,
+                    destination: *owner,
+                    amount,
+                    nonce: approval.nonce,
+                };
+                vec![
+                    message.verify_instruction(owner, &approval.signature),
+                    instruction::with_owner_approval(ix, owner),
+                ]
+            }
+            None => vec![ix],
+        }
+    }
+
+    /// The keypairs signing DART authorized transactions.
+    fn signers(&self) -> Vec<&Keypair> {
+        let mut signers = vec![&self.signing_keys];
+        if self.multisig.is_some() {
+            signers.extend(self.cosigning_keys.iter());
+        }
+        signers
+    }
 }
 
 #[async_trait::async_trait]
 impl SolanaDriver for SolanaRpc {
     /// Create a new vault with the given seed and owner.
-    async fn create_vault(&self, seed: &str, owner: &Pubkey) -> Result<(Pubkey, Signature)> {
-        let dart = &self.signing_keys.pubkey();
-        let (space, lamports) = self.calculate_rent();
+    async fn create_vault(
+        &self,
+        seed: &str,
+        owner: &Pubkey,
+        mode: AuthorityMode,
+        unlock_at: Option<UnlockAt>,
+    ) -> Result<(Pubkey, Signature)> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
 
         // Generate the deposit address
-        let pda = Pubkey::create_with_seed(dart, seed, &id())
-            .map_err(|err| Error::PubkeyWithSeedError(err.to_string()))?;
+        let (pda, _) = find_vault_address(dart, seed);
 
-        // Need to create account and init vault record in one transaction
-        let instructions = &[
-            system_instruction::create_account_with_seed(
-                dart,
-                &pda,
-                dart,
-                seed,
-                lamports,
-                space,
-                &id(),
-            ),
-            instruction::initialize(&pda, dart, owner),
-        ];
+        // The program creates the account and inits the vault record
+        let instructions = &[self.authorize(instruction::create_vault(
+            payer, dart, owner, seed, mode, unlock_at,
+        ))];
 
         // Build transaction
         let transaction = Transaction::new_signed_with_payer(
             instructions,
-            Some(dart),
-            &[&self.signing_keys],
+            Some(payer),
+            &self.signers(),
             self.get_latest_blockhash()?,
         );
 
@@ -66,7 +147,7 @@ impl SolanaDriver for SolanaRpc {
         let signature = self
             .rpc_client
             .send_and_confirm_transaction(&transaction)
-            .map_err(|err| Error::CreateVaultError(err.kind.to_string()))?;
+            .map_err(|err| Error::from_transaction(err, Error::CreateVaultError))?;
 
         Ok((pda, signature))
     }
@@ -80,6 +161,13 @@ impl SolanaDriver for SolanaRpc {
         Ok(account)
     }
 
+    /// Get the decoded record in a vault.
+    async fn get_vault_record(&self, pda: &Pubkey) -> Result<VaultRecord> {
+        let account = self.get_vault_account(pda).await?;
+        VaultRecord::unpack(&account.data)
+            .map_err(|err| Error::DecodeVaultRecordError(err.to_string()))
+    }
+
     /// Transfer ownership of a vault.
     async fn change_vault_owner(
         &self,
@@ -87,30 +175,550 @@ impl SolanaDriver for SolanaRpc {
         owner: &Pubkey,
         new_owner: &Pubkey,
     ) -> Result<Signature> {
-        let dart = &self.signing_keys.pubkey();
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
 
         let transaction = Transaction::new_signed_with_payer(
-            &[instruction::transfer_owner(&pda, &dart, &owner, &new_owner)],
-            Some(dart),
-            &[&self.signing_keys],
+            &[self.authorize(instruction::transfer_owner(
+                pda,
+                dart,
+                owner,
+                new_owner,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
             self.get_latest_blockhash()?,
         );
 
         let signature: Signature = self
             .rpc_client
             .send_and_confirm_transaction(&transaction)
-            .map_err(|err| Error::ChangeVaultOwnerError(err.kind.to_string()))?;
+            .map_err(|err| Error::from_transaction(err, Error::ChangeVaultOwnerError))?;
+
+        Ok(signature)
+    }
+
+    /// Propose a new owner for a vault.
+    async fn propose_vault_owner(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        new_owner: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::propose_owner(
+                pda,
+                dart,
+                owner,
+                new_owner,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::ProposeVaultOwnerError))?;
 
         Ok(signature)
     }
 
-    /// Close a vault and drain lamports to the current owner.
-    async fn close_vault(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature> {
+    /// Build a transaction accepting a vault ownership proposal, with the DART as fee payer.
+    async fn accept_vault_owner(&self, pda: &Pubkey, new_owner: &Pubkey) -> Result<Transaction> {
         let dart = &self.signing_keys.pubkey();
 
+        let mut transaction =
+            Transaction::new_with_payer(&[instruction::accept_owner(pda, new_owner)], Some(dart));
+        transaction.partial_sign(&[&self.signing_keys], self.get_latest_blockhash()?);
+
+        Ok(transaction)
+    }
+
+    /// Cancel a pending vault ownership proposal.
+    async fn cancel_vault_owner_proposal(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
         let transaction = Transaction::new_signed_with_payer(
-            &[instruction::close_account(pda, dart, owner)],
-            Some(dart),
+            &[self.authorize(instruction::cancel_owner_proposal(
+                pda,
+                dart,
+                owner,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::CancelVaultOwnerError))?;
+
+        Ok(signature)
+    }
+
+    /// Close a vault and pay its balance above rent to the current owner.
+    async fn close_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        approval: Option<&Approval>,
+        wallets: &[Pubkey],
+    ) -> Result<(Signature, Option<u64>)> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+        let fee_collector = &self.get_program_config()?.fee_collector;
+
+        let ix = instruction::with_co_owner_wallets(
+            self.authorize(instruction::close_account(
+                pda,
+                dart,
+                owner,
+                fee_collector,
+                AuthorityMode::Custodial,
+            )),
+            wallets,
+        );
+        let instructions =
+            self.with_approval(ix, pda, owner, approval, ApprovalAction::CloseAccount, 0);
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::CloseVaultError))?;
+
+        let fee = self.get_payout_fee(&signature);
+        Ok((signature, fee))
+    }
+
+    /// Withdraw lamports from a vault to the current owner.
+    async fn withdraw_from_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        lamports: u64,
+        approval: Option<&Approval>,
+        wallets: &[Pubkey],
+    ) -> Result<(Signature, Option<u64>)> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+        let fee_collector = &self.get_program_config()?.fee_collector;
+
+        let ix = instruction::with_co_owner_wallets(
+            self.authorize(instruction::withdraw(
+                pda,
+                dart,
+                owner,
+                fee_collector,
+                lamports,
+                AuthorityMode::Custodial,
+            )),
+            wallets,
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &self.with_approval(ix, pda, owner, approval, ApprovalAction::Withdraw, lamports),
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::WithdrawVaultError))?;
+
+        let fee = self.get_payout_fee(&signature);
+        Ok((signature, fee))
+    }
+
+    /// Freeze a vault for a compliance hold.
+    async fn freeze_vault(&self, pda: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::freeze(pda, dart))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::FreezeVaultError))?;
+
+        Ok(signature)
+    }
+
+    /// Lift a compliance hold from a vault.
+    async fn thaw_vault(&self, pda: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::thaw(pda, dart))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::ThawVaultError))?;
+
+        Ok(signature)
+    }
+
+    /// List the vaults of the configured DART, whatever their record version.
+    async fn find_vaults(&self) -> Result<Vec<Pubkey>> {
+        // The DART key follows the version byte and owner key in every
+        // vault record version.
+        const DART_OFFSET: usize = 1 + 32;
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                DART_OFFSET,
+                self.authority().to_bytes().to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&vault::id(), config)
+            .map_err(|err| Error::FindVaultsError(err.kind.to_string()))?;
+
+        // The memcmp also matches metadata, allowance and other companion
+        // accounts whose bytes happen to hold the DART key there, so each
+        // candidate must decode as a live record whose address is its own:
+        // derived from its seed, or a wallet address (on curve, which no
+        // companion PDA is) for vaults not created at a program address.
+        // Multisigs are wallet-allocated too; the program tells them apart
+        // by size.
+        Ok(accounts
+            .into_iter()
+            .filter(|(pda, account)| {
+                if account.data.len() == Multisig::LEN {
+                    return false;
+                }
+                let Ok(record) = VaultRecord::unpack(&account.data) else {
+                    return false;
+                };
+                if !(VaultRecord::V1_VERSION..=VaultRecord::CURRENT_VERSION)
+                    .contains(&record.version)
+                {
+                    return false;
+                }
+                if record.bump == 0 {
+                    return pda.is_on_curve();
+                }
+                Pubkey::create_program_address(&record.signer_seeds(), &vault::id())
+                    .is_ok_and(|address| address == *pda)
+            })
+            .map(|(pda, _)| pda)
+            .collect())
+    }
+
+    /// Move a batch of vaults to a new intermediary in a single transaction,
+    /// migrating records written by older versions first.
+    async fn rotate_vault_intermediary(
+        &self,
+        pdas: &[Pubkey],
+        new_dart: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        // Migration leaves current records untouched.
+        let instructions: Vec<Instruction> = pdas
+            .iter()
+            .flat_map(|pda| {
+                [
+                    instruction::migrate(pda, payer),
+                    self.authorize(instruction::rotate_intermediary(pda, dart, new_dart)),
+                ]
+            })
+            .collect();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::RotateIntermediaryError))?;
+
+        Ok(signature)
+    }
+
+    /// Approve a delegate to withdraw from a vault to a recipient, with the
+    /// DART paying the allowance rent.
+    async fn approve_vault_delegate(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        delegate: &Pubkey,
+        lamports: u64,
+        expires_at: Option<UnixTimestamp>,
+        recipient: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::approve(
+                payer,
+                pda,
+                dart,
+                owner,
+                delegate,
+                lamports,
+                expires_at,
+                recipient,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::ApproveDelegateError))?;
+
+        Ok(signature)
+    }
+
+    /// Revoke a delegate's allowance on a vault, returning the allowance rent to the DART.
+    async fn revoke_vault_delegate(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        delegate: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::revoke(
+                pda,
+                dart,
+                owner,
+                delegate,
+                payer,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::RevokeDelegateError))?;
+
+        Ok(signature)
+    }
+
+    /// List the allowances drawing from a vault.
+    async fn find_vault_allowances(&self, pda: &Pubkey) -> Result<Vec<Allowance>> {
+        // The vault key follows the version and bump bytes in an allowance.
+        const VAULT_OFFSET: usize = 1 + 1;
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(Allowance::LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(VAULT_OFFSET, pda.to_bytes().to_vec())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&vault::id(), config)
+            .map_err(|err| Error::FindAllowancesError(err.kind.to_string()))?;
+
+        accounts
+            .iter()
+            .map(|(_, account)| {
+                Allowance::unpack(&account.data)
+                    .map_err(|err| Error::FindAllowancesError(err.to_string()))
+            })
+            .collect()
+    }
+
+    /// Set the withdrawal limit of a vault, or lift it.
+    async fn set_vault_withdrawal_limit(
+        &self,
+        pda: &Pubkey,
+        limit: Option<WithdrawalLimit>,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::set_withdrawal_limit(pda, dart, limit))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::SetWithdrawalLimitError))?;
+
+        Ok(signature)
+    }
+
+    /// Move lamports from one vault to another vault of the same DART.
+    async fn transfer_between_vaults(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        destination: &Pubkey,
+        lamports: u64,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::internal_transfer(
+                pda,
+                destination,
+                dart,
+                owner,
+                lamports,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::TransferVaultError))?;
+
+        Ok(signature)
+    }
+
+    /// Set the guardian of a vault, or remove it.
+    async fn set_vault_guardian(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        guardian: Option<Pubkey>,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::set_guardian(
+                pda,
+                dart,
+                owner,
+                guardian,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::SetGuardianError))?;
+
+        Ok(signature)
+    }
+
+    /// Build a DART signed transaction starting a vault recovery, awaiting the
+    /// guardian's signature, or those of the given multisig guardian signers.
+    async fn start_vault_recovery(
+        &self,
+        pda: &Pubkey,
+        guardian: &Pubkey,
+        new_owner: &Pubkey,
+        signers: &[Pubkey],
+    ) -> Result<Transaction> {
+        let payer = &self.signing_keys.pubkey();
+
+        let mut ix = instruction::start_recovery(pda, guardian, new_owner);
+        if !signers.is_empty() {
+            ix = instruction::with_multisig_signers(ix, guardian, signers);
+        }
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(payer));
+        transaction.partial_sign(&[&self.signing_keys], self.get_latest_blockhash()?);
+
+        Ok(transaction)
+    }
+
+    /// Cancel a pending vault recovery.
+    async fn cancel_vault_recovery(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::cancel_recovery(
+                pda,
+                dart,
+                owner,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::CancelRecoveryError))?;
+
+        Ok(signature)
+    }
+
+    /// Complete a vault recovery whose challenge period is over.
+    async fn complete_vault_recovery(&self, pda: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction::complete_recovery(pda)],
+            Some(payer),
             &[&self.signing_keys],
             self.get_latest_blockhash()?,
         );
@@ -118,11 +726,476 @@ impl SolanaDriver for SolanaRpc {
         let signature = self
             .rpc_client
             .send_and_confirm_transaction(&transaction)
-            .map_err(|err| Error::CloseVaultError(err.kind.to_string()))?;
+            .map_err(|err| Error::from_transaction(err, Error::CompleteRecoveryError))?;
+
+        Ok(signature)
+    }
+
+    /// Find the token accounts of a vault under SPL Token and Token-2022,
+    /// with the token program of each.
+    async fn find_vault_token_accounts(&self, pda: &Pubkey) -> Result<Vec<(Pubkey, TokenAccount)>> {
+        // The token account owner follows the mint.
+        const OWNER_OFFSET: usize = 32;
+
+        let mut token_accounts = vec![];
+        for token_program in [spl_token::id(), spl_token_2022::id()] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    OWNER_OFFSET,
+                    pda.to_bytes().to_vec(),
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+
+            let accounts = self
+                .rpc_client
+                .get_program_accounts_with_config(&token_program, config)
+                .map_err(|err| Error::FindTokenAccountsError(err.kind.to_string()))?;
+
+            // Token-2022 mints may match the filter too; only keep token accounts.
+            token_accounts.extend(accounts.iter().filter_map(|(_, account)| {
+                StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                    .ok()
+                    .map(|state| (token_program, state.base))
+            }));
+        }
+        Ok(token_accounts)
+    }
+
+    /// Get a token mint with its token program.
+    async fn get_token_mint(&self, mint: &Pubkey) -> Result<(Pubkey, Mint)> {
+        let account = self
+            .rpc_client
+            .get_account(mint)
+            .map_err(|err| Error::GetTokenMintError(err.kind.to_string()))?;
+        let state = StateWithExtensions::<Mint>::unpack(&account.data)
+            .map_err(|err| Error::GetTokenMintError(err.to_string()))?;
+        Ok((account.owner, state.base))
+    }
+
+    /// Withdraw tokens from a vault to the owner's associated token account,
+    /// creating it if needed.
+    async fn withdraw_vault_token(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                create_associated_token_account_idempotent(payer, owner, mint, token_program),
+                self.authorize(instruction::withdraw_token(
+                    pda,
+                    dart,
+                    owner,
+                    mint,
+                    token_program,
+                    amount,
+                    AuthorityMode::Custodial,
+                )),
+            ],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::WithdrawTokenError))?;
+
+        Ok(signature)
+    }
+
+    /// Create a new custodial vault with the given seed, co-owned by the given
+    /// co-owners.
+    async fn create_co_owned_vault(
+        &self,
+        seed: &str,
+        co_owners: Vec<CoOwner>,
+        threshold: u8,
+        unlock_at: Option<UnlockAt>,
+    ) -> Result<(Pubkey, Signature)> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        // The vault is created owned by its co-owner list, filled in right after
+        let (pda, _) = find_vault_address(dart, seed);
+        let (owner, _) = find_co_owners_address(&pda);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                self.authorize(instruction::create_vault(
+                    payer,
+                    dart,
+                    &owner,
+                    seed,
+                    AuthorityMode::Custodial,
+                    unlock_at,
+                )),
+                self.authorize(instruction::set_co_owners(
+                    payer,
+                    &pda,
+                    dart,
+                    &owner,
+                    co_owners,
+                    threshold,
+                    AuthorityMode::Custodial,
+                )),
+            ],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::CreateVaultError))?;
+
+        Ok((pda, signature))
+    }
+
+    /// Make a vault co-owned, or replace its co-owners.
+    async fn set_vault_co_owners(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        co_owners: Vec<CoOwner>,
+        threshold: u8,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::set_co_owners(
+                payer,
+                pda,
+                dart,
+                owner,
+                co_owners,
+                threshold,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::SetCoOwnersError))?;
+
+        Ok(signature)
+    }
+
+    /// Get the co-owner list of a vault, if it is co-owned.
+    async fn get_vault_co_owners(&self, pda: &Pubkey) -> Result<Option<CoOwners>> {
+        let (address, _) = find_co_owners_address(pda);
+        if self.get_vault_record(pda).await?.owner != address {
+            return Ok(None);
+        }
+        let account = self
+            .rpc_client
+            .get_account(&address)
+            .map_err(|err| Error::GetCoOwnersError(err.to_string()))?;
+        let co_owners = CoOwners::unpack(&account.data)
+            .map_err(|err| Error::GetCoOwnersError(err.to_string()))?;
+        Ok(Some(co_owners))
+    }
+
+    /// Transfer a co-owner's share of a co-owned vault.
+    async fn transfer_vault_share(
+        &self,
+        pda: &Pubkey,
+        co_owner: &Pubkey,
+        new_co_owner: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::transfer_share(
+                pda,
+                dart,
+                co_owner,
+                new_co_owner,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::TransferShareError))?;
+
+        Ok(signature)
+    }
+
+    /// Set the beneficiary of a vault, or remove it.
+    async fn set_vault_beneficiary(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        inheritance: Option<Inheritance>,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::set_beneficiary(
+                pda,
+                dart,
+                owner,
+                inheritance,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::SetBeneficiaryError))?;
+
+        Ok(signature)
+    }
+
+    /// Attach metadata to a vault, replacing any previous metadata.
+    async fn set_vault_metadata(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        metadata: VaultMetadata,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::set_metadata(
+                payer,
+                pda,
+                dart,
+                owner,
+                metadata,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::SetMetadataError))?;
+
+        Ok(signature)
+    }
+
+    /// Remove the metadata of a vault, returning its rent to the DART.
+    async fn clear_vault_metadata(&self, pda: &Pubkey, owner: &Pubkey) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::clear_metadata(
+                pda,
+                dart,
+                owner,
+                payer,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::ClearMetadataError))?;
+
+        Ok(signature)
+    }
+
+    /// Get the metadata attached to a vault, if any.
+    async fn get_vault_metadata(&self, pda: &Pubkey) -> Result<Option<VaultMetadata>> {
+        let (address, _) = find_metadata_address(pda);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&address, self.rpc_client.commitment())
+            .map_err(|err| Error::GetMetadataError(err.to_string()))?
+            .value;
+        account
+            .map(|account| Metadata::unpack(&account.data).map(|metadata| metadata.metadata))
+            .transpose()
+            .map_err(|err| Error::GetMetadataError(err.to_string()))
+    }
+
+    /// Find the stake accounts a vault is the withdraw authority of, with
+    /// their balance.
+    async fn find_vault_stakes(&self, pda: &Pubkey) -> Result<Vec<(Pubkey, u64, StakeStateV2)>> {
+        // The withdrawer follows the state tag, rent-exempt reserve and staker.
+        const WITHDRAWER_OFFSET: usize = 44;
+
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                WITHDRAWER_OFFSET,
+                pda.to_bytes().to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&stake::program::id(), config)
+            .map_err(|err| Error::FindStakesError(err.kind.to_string()))?;
+
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                account
+                    .deserialize_data::<StakeStateV2>()
+                    .ok()
+                    .map(|state| (address, account.lamports, state))
+            })
+            .collect())
+    }
+
+    /// Stake lamports from a vault in a new stake account at the given index,
+    /// delegated to a validator.
+    async fn stake_from_vault(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        vote_account: &Pubkey,
+        index: u64,
+        lamports: u64,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+        let (stake_account, _) = find_stake_address(pda, index);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                self.authorize(instruction::create_stake(
+                    payer,
+                    pda,
+                    dart,
+                    owner,
+                    index,
+                    lamports,
+                    AuthorityMode::Custodial,
+                )),
+                self.authorize(instruction::delegate_stake(
+                    pda,
+                    dart,
+                    owner,
+                    &stake_account,
+                    vote_account,
+                    AuthorityMode::Custodial,
+                )),
+            ],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::StakeError))?;
 
         Ok(signature)
     }
 
+    /// Deactivate a vault stake account.
+    async fn deactivate_vault_stake(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        stake_account: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::deactivate_stake(
+                pda,
+                dart,
+                owner,
+                stake_account,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::DeactivateStakeError))?;
+
+        Ok(signature)
+    }
+
+    /// Withdraw an inactive vault stake account back into the vault.
+    async fn withdraw_vault_stake(
+        &self,
+        pda: &Pubkey,
+        owner: &Pubkey,
+        stake_account: &Pubkey,
+    ) -> Result<Signature> {
+        let payer = &self.signing_keys.pubkey();
+        let dart = &self.authority();
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[self.authorize(instruction::withdraw_stake(
+                pda,
+                dart,
+                owner,
+                stake_account,
+                AuthorityMode::Custodial,
+            ))],
+            Some(payer),
+            &self.signers(),
+            self.get_latest_blockhash()?,
+        );
+
+        let signature = self
+            .rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|err| Error::from_transaction(err, Error::WithdrawStakeError))?;
+
+        Ok(signature)
+    }
+
+    /// Whether the vault program is globally paused.
+    async fn is_program_paused(&self) -> Result<bool> {
+        Ok(self.get_program_config()?.paused)
+    }
+
     /// Check the solana connection.
     async fn health_check(&self) -> Result<()> {
         self.rpc_client
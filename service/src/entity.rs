@@ -1,8 +1,15 @@
+use vault::{
+    approval::SIGNATURE_LEN,
+    state::{CoOwner, Inheritance, PendingLimit, Recovery, VaultMetadata, WithdrawalLimit},
+};
+
 /// Represents a Solana account balance.
 #[derive(Debug)]
 pub struct Balance {
     pub pda: String,
     pub lamports: u64,
+    pub frozen: bool,                    // Vault frozen or program paused
+    pub metadata: Option<VaultMetadata>, // None when the vault has no metadata
 }
 
 /// Represents transaction signature hash.
@@ -11,6 +18,92 @@ pub struct Signature {
     pub hash: String,
 }
 
+/// Represents a partially signed transaction awaiting further signatures.
+#[derive(Debug)]
+pub struct PendingTransaction {
+    pub encoded: String, // Base64 encoded wire transaction
+}
+
+/// Represents lamports paid out of a vault.
+#[derive(Debug)]
+pub struct Payout {
+    pub signature: Signature,
+    pub fee: Option<u64>, // Lamports taken by the fee collector, if known
+}
+
+/// Represents an owner's off-chain approval of a vault instruction.
+#[derive(Debug)]
+pub struct Approval {
+    pub nonce: u64,                     // Replay protection nonce
+    pub signature: [u8; SIGNATURE_LEN], // Owner ed25519 signature
+}
+
+/// Represents a delegate allowed to withdraw from a vault.
+#[derive(Debug)]
+pub struct Delegate {
+    pub delegate: String,
+    pub lamports: u64,           // Lamports the delegate may still withdraw
+    pub expires_at: Option<i64>, // Unix timestamp the allowance expires at
+    pub recipient: String,       // The only account the delegate may withdraw to
+}
+
+/// Represents the withdrawal limit of a vault and its use in the current window.
+#[derive(Debug)]
+pub struct Limits {
+    pub limit: Option<WithdrawalLimit>, // None when withdrawals are unlimited
+    pub window_start: i64,              // Unix timestamp the current window started at
+    pub withdrawn: u64,                 // Lamports withdrawn in the current window
+    pub pending: Option<PendingLimit>,  // Raised limit awaiting its delay
+}
+
+/// Represents the owners of a vault, a single owner holding one share.
+#[derive(Debug)]
+pub struct Owners {
+    pub owners: Vec<CoOwner>,
+    pub threshold: u8, // Owners required to approve in place of the owner
+}
+
+/// Represents the beneficiary of a vault and the owner's last activity.
+#[derive(Debug)]
+pub struct BeneficiaryStatus {
+    pub inheritance: Option<Inheritance>, // None when the vault has no beneficiary
+    pub last_activity: i64,               // Unix timestamp of the last owner signature
+}
+
+/// Represents the balance of a vault token account.
+#[derive(Debug)]
+pub struct TokenBalance {
+    pub mint: String,
+    pub token_program: String, // SPL Token or Token-2022
+    pub amount: u64,           // Base units of the mint
+    pub decimals: u8,
+}
+
+/// Represents a stake account of a vault.
+#[derive(Debug)]
+pub struct StakePosition {
+    pub stake_account: String,
+    pub lamports: u64,                   // Rent-exempt reserve included
+    pub voter: Option<String>,           // None until delegated
+    pub activation_epoch: Option<u64>,   // None until delegated
+    pub deactivation_epoch: Option<u64>, // None while active
+}
+
+/// Represents the guardian of a vault and any pending owner recovery.
+#[derive(Debug)]
+pub struct RecoveryStatus {
+    pub guardian: Option<String>,   // None when the vault has no guardian
+    pub recovery: Option<Recovery>, // Recovery awaiting its challenge period
+}
+
+/// Represents the progress of a bulk intermediary rotation, after each batch.
+#[derive(Debug)]
+pub struct RotationProgress {
+    pub signature: Signature, // Signature of the batch transaction
+    pub rotated: usize,       // Vaults rotated so far
+    pub total: usize,         // Vaults to rotate
+}
+
 /// Represents a newly created account.
 #[derive(Debug)]
 pub struct VaultAccount {
@@ -17,7 +17,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     log::info!("Solana rpc url = {}", config.rpc_url);
 
     // Wire up API
-    let rpc = SolanaRpc::new(config.rpc_url, config.keypair);
+    let rpc = SolanaRpc::new(
+        config.rpc_url,
+        config.keypair,
+        config.multisig,
+        config.cosigner_keypairs,
+    );
     let driver = Arc::new(Box::new(rpc) as Box<dyn SolanaDriver>);
     let api = FauxstodianApi::new(Service::new(Arc::clone(&driver)));
 
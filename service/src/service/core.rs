@@ -1,13 +1,50 @@
-use crate::entity::{Balance, Signature, VaultAccount};
+use crate::entity::{
+    Balance, BeneficiaryStatus, Delegate, Limits, Owners, Payout, PendingTransaction,
+    RecoveryStatus, RotationProgress, Signature, StakePosition, TokenBalance, VaultAccount,
+};
+use solana_sdk::stake::state::StakeStateV2;
+use tokio::sync::mpsc::Sender;
+use vault::state::{
+    find_stake_address, AuthorityMode, CoOwner, Inheritance, UnlockAt, VaultMetadata,
+    WithdrawalLimit,
+};
 
-use super::{Result, Service};
+use super::{Error, Result, Service};
+
+/// Vaults rotated per transaction, keeping transactions within the size limit.
+const ROTATION_BATCH_SIZE: usize = 8;
 
 impl Service {
-    /// Create and initialize a solana vault PDA.
-    pub async fn create_account(&self, seed: &str, owner: &str) -> Result<VaultAccount> {
+    /// Create and initialize a solana vault PDA. A vault with co-owners is
+    /// owned by them in place of the single owner. Vaults are created
+    /// custodial, since the service signs every later instruction as the DART
+    /// alone.
+    pub async fn create_account(
+        &self,
+        seed: &str,
+        owner: &str,
+        mode: AuthorityMode,
+        unlock_at: Option<UnlockAt>,
+        co_owners: &[(String, u32)],
+        threshold: u32,
+    ) -> Result<VaultAccount> {
         let seed = self.validate_seed(seed)?;
-        let owner = self.parse_pubkey(owner)?;
-        let (pda, signature) = self.driver.create_vault(&seed, &owner).await?;
+        if mode != AuthorityMode::Custodial {
+            return Err(Error::InvalidArgument {
+                message: format!("unsupported authority mode: {mode:?}"),
+            });
+        }
+        let (pda, signature) = if co_owners.is_empty() {
+            let owner = self.parse_pubkey(owner)?;
+            self.driver
+                .create_vault(&seed, &owner, mode, unlock_at)
+                .await?
+        } else {
+            let (co_owners, threshold) = self.parse_co_owners(co_owners, threshold)?;
+            self.driver
+                .create_co_owned_vault(&seed, co_owners, threshold, unlock_at)
+                .await?
+        };
         Ok(VaultAccount {
             pda: pda.to_string(),
             signature: Some(Signature {
@@ -18,11 +55,16 @@ impl Service {
 
     /// Query for solana account balance.
     pub async fn get_balance(&self, pda: &str) -> Result<Balance> {
-        let pda = self.parse_pubkey(&pda)?;
+        let pda = self.parse_pubkey(pda)?;
         let account = self.driver.get_vault_account(&pda).await?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        let paused = self.driver.is_program_paused().await?;
+        let metadata = self.driver.get_vault_metadata(&pda).await?;
         Ok(Balance {
             pda: pda.to_string(),
             lamports: account.lamports,
+            frozen: record.frozen || paused,
+            metadata,
         })
     }
 
@@ -34,6 +76,7 @@ impl Service {
         new_owner: &str,
     ) -> Result<Signature> {
         let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
         let owner = self.parse_pubkey(owner)?;
         let new_owner = self.parse_pubkey(new_owner)?;
         let signature = self
@@ -45,13 +88,653 @@ impl Service {
         })
     }
 
-    /// Close a vault PDA.
-    pub async fn close_account(&self, pda: &str, owner: &str) -> Result<Signature> {
+    /// Propose a new owner for a vault PDA.
+    pub async fn propose_owner(
+        &self,
+        pda: &str,
+        owner: &str,
+        new_owner: &str,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let new_owner = self.parse_pubkey(new_owner)?;
+        let signature = self
+            .driver
+            .propose_vault_owner(&pda, &owner, &new_owner)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Prepare a transaction for the proposed owner of a vault PDA to sign.
+    pub async fn accept_owner(&self, pda: &str, new_owner: &str) -> Result<PendingTransaction> {
+        let pda = self.parse_pubkey(pda)?;
+        let new_owner = self.parse_pubkey(new_owner)?;
+        let transaction = self.driver.accept_vault_owner(&pda, &new_owner).await?;
+        Ok(PendingTransaction {
+            encoded: self.encode_transaction(&transaction)?,
+        })
+    }
+
+    /// Cancel a pending ownership proposal for a vault PDA.
+    pub async fn cancel_owner_proposal(&self, pda: &str, owner: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let signature = self
+            .driver
+            .cancel_vault_owner_proposal(&pda, &owner)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Close a vault PDA. Vaults requiring the owner signature need an owner
+    /// approval (nonce and signature).
+    pub async fn close_account(
+        &self,
+        pda: &str,
+        owner: &str,
+        approval: Option<(u64, &[u8])>,
+    ) -> Result<Payout> {
+        let pda = self.parse_pubkey(pda)?;
+        let approval = match approval {
+            Some((nonce, signature)) => Some(self.parse_approval(nonce, signature)?),
+            None => {
+                self.require_custodial(&pda).await?;
+                None
+            }
+        };
+        let (owner, wallets) = self.resolve_payee(&pda, owner).await?;
+        let (signature, fee) = self
+            .driver
+            .close_vault(&pda, &owner, approval.as_ref(), &wallets)
+            .await?;
+        Ok(Payout {
+            signature: Signature {
+                hash: signature.to_string(),
+            },
+            fee,
+        })
+    }
+
+    /// Withdraw lamports from a vault PDA to the owner. Vaults requiring the
+    /// owner signature need an owner approval (nonce and signature).
+    pub async fn withdraw(
+        &self,
+        pda: &str,
+        owner: &str,
+        lamports: u64,
+        approval: Option<(u64, &[u8])>,
+    ) -> Result<Payout> {
+        let pda = self.parse_pubkey(pda)?;
+        let approval = match approval {
+            Some((nonce, signature)) => Some(self.parse_approval(nonce, signature)?),
+            None => {
+                self.require_custodial(&pda).await?;
+                None
+            }
+        };
+        let (owner, wallets) = self.resolve_payee(&pda, owner).await?;
+        let lamports = self.validate_lamports(lamports)?;
+        let (signature, fee) = self
+            .driver
+            .withdraw_from_vault(&pda, &owner, lamports, approval.as_ref(), &wallets)
+            .await?;
+        Ok(Payout {
+            signature: Signature {
+                hash: signature.to_string(),
+            },
+            fee,
+        })
+    }
+
+    /// Freeze a vault PDA for a compliance hold.
+    pub async fn freeze_vault(&self, pda: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        let signature = self.driver.freeze_vault(&pda).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Lift a compliance hold from a vault PDA.
+    pub async fn thaw_vault(&self, pda: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        let signature = self.driver.thaw_vault(&pda).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Approve a delegate to withdraw up to a number of lamports from a vault
+    /// PDA to a recipient.
+    pub async fn approve_delegate(
+        &self,
+        pda: &str,
+        owner: &str,
+        delegate: &str,
+        lamports: u64,
+        expires_at: Option<i64>,
+        recipient: &str,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let delegate = self.parse_pubkey(delegate)?;
+        let lamports = self.validate_lamports(lamports)?;
+        let recipient = self.parse_pubkey(recipient)?;
+        let signature = self
+            .driver
+            .approve_vault_delegate(&pda, &owner, &delegate, lamports, expires_at, &recipient)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Revoke a delegate's allowance on a vault PDA.
+    pub async fn revoke_delegate(
+        &self,
+        pda: &str,
+        owner: &str,
+        delegate: &str,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let delegate = self.parse_pubkey(delegate)?;
+        let signature = self
+            .driver
+            .revoke_vault_delegate(&pda, &owner, &delegate)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// List the delegates of a vault PDA with their remaining allowances.
+    /// Allowances granted by a previous owner have lapsed and are left out.
+    pub async fn list_delegates(&self, pda: &str) -> Result<Vec<Delegate>> {
+        let pda = self.parse_pubkey(pda)?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        let allowances = self.driver.find_vault_allowances(&pda).await?;
+        Ok(allowances
+            .into_iter()
+            .filter(|allowance| allowance.owner == record.owner)
+            .map(|allowance| Delegate {
+                delegate: allowance.delegate.to_string(),
+                lamports: allowance.amount,
+                expires_at: allowance.expires_at,
+                recipient: allowance.recipient.to_string(),
+            })
+            .collect())
+    }
+
+    /// Get the withdrawal limit of a vault PDA.
+    pub async fn get_limits(&self, pda: &str) -> Result<Limits> {
+        let pda = self.parse_pubkey(pda)?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        Ok(Limits {
+            limit: record.limit,
+            window_start: record.window_start,
+            withdrawn: record.withdrawn,
+            pending: record.pending_limit,
+        })
+    }
+
+    /// Set the withdrawal limit of a vault PDA, or lift it. Raising the limit
+    /// takes effect after the program's raise delay.
+    pub async fn set_limits(&self, pda: &str, limit: Option<WithdrawalLimit>) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        if let Some(limit) = limit {
+            if limit.window <= 0 {
+                return Err(Error::InvalidArgument {
+                    message: "limit window must be greater than zero".to_string(),
+                });
+            }
+        }
+        let signature = self.driver.set_vault_withdrawal_limit(&pda, limit).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Move lamports from one custodial vault PDA to another, without fees.
+    pub async fn transfer(
+        &self,
+        pda: &str,
+        owner: &str,
+        destination: &str,
+        lamports: u64,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let destination = self.parse_pubkey(destination)?;
+        let lamports = self.validate_lamports(lamports)?;
+        let signature = self
+            .driver
+            .transfer_between_vaults(&pda, &owner, &destination, lamports)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Set the guardian able to recover a custodial vault PDA, or remove it.
+    pub async fn set_guardian(
+        &self,
+        pda: &str,
+        owner: &str,
+        guardian: Option<&str>,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let guardian = guardian.map(|g| self.parse_pubkey(g)).transpose()?;
+        let signature = self
+            .driver
+            .set_vault_guardian(&pda, &owner, guardian)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Build a transaction starting the recovery of a vault PDA to a new
+    /// owner, for its guardian (or the given multisig signers) to sign.
+    pub async fn start_recovery(
+        &self,
+        pda: &str,
+        new_owner: &str,
+        signers: &[String],
+    ) -> Result<PendingTransaction> {
+        let pda = self.parse_pubkey(pda)?;
+        let new_owner = self.parse_pubkey(new_owner)?;
+        let signers = signers
+            .iter()
+            .map(|s| self.parse_pubkey(s))
+            .collect::<Result<Vec<_>>>()?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        let guardian = record.guardian.ok_or_else(|| Error::FailedPrecondition {
+            message: format!("vault has no guardian: {pda}"),
+        })?;
+        let transaction = self
+            .driver
+            .start_vault_recovery(&pda, &guardian, &new_owner, &signers)
+            .await?;
+        Ok(PendingTransaction {
+            encoded: self.encode_transaction(&transaction)?,
+        })
+    }
+
+    /// Cancel a pending recovery of a custodial vault PDA.
+    pub async fn cancel_recovery(&self, pda: &str, owner: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let signature = self.driver.cancel_vault_recovery(&pda, &owner).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Complete the recovery of a vault PDA once its challenge period is over.
+    pub async fn complete_recovery(&self, pda: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        let signature = self.driver.complete_vault_recovery(&pda).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Get the guardian of a vault PDA and any pending recovery.
+    pub async fn get_recovery(&self, pda: &str) -> Result<RecoveryStatus> {
+        let pda = self.parse_pubkey(pda)?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        Ok(RecoveryStatus {
+            guardian: record.guardian.map(|g| g.to_string()),
+            recovery: record.recovery,
+        })
+    }
+
+    /// Get the token balances held by a vault PDA.
+    pub async fn get_token_balances(&self, pda: &str) -> Result<Vec<TokenBalance>> {
+        let pda = self.parse_pubkey(pda)?;
+        let mut balances = vec![];
+        for (token_program, account) in self.driver.find_vault_token_accounts(&pda).await? {
+            let (_, mint) = self.driver.get_token_mint(&account.mint).await?;
+            balances.push(TokenBalance {
+                mint: account.mint.to_string(),
+                token_program: token_program.to_string(),
+                amount: account.amount,
+                decimals: mint.decimals,
+            });
+        }
+        Ok(balances)
+    }
+
+    /// Withdraw tokens from a custodial vault PDA to the owner.
+    pub async fn withdraw_token(
+        &self,
+        pda: &str,
+        owner: &str,
+        mint: &str,
+        amount: u64,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let mint = self.parse_pubkey(mint)?;
+        if amount == 0 {
+            return Err(Error::InvalidArgument {
+                message: "amount must be greater than zero".to_string(),
+            });
+        }
+        let (token_program, _) = self.driver.get_token_mint(&mint).await?;
+        let signature = self
+            .driver
+            .withdraw_vault_token(&pda, &owner, &mint, &token_program, amount)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Stake lamports from a custodial vault PDA with a validator, in a new
+    /// stake account.
+    pub async fn stake(
+        &self,
+        pda: &str,
+        owner: &str,
+        vote_account: &str,
+        lamports: u64,
+    ) -> Result<(Signature, String)> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let vote_account = self.parse_pubkey(vote_account)?;
+        let lamports = self.validate_lamports(lamports)?;
+
+        // Use the first stake account index not taken by an open position.
+        let taken: Vec<_> = self
+            .driver
+            .find_vault_stakes(&pda)
+            .await?
+            .into_iter()
+            .map(|(address, _, _)| address)
+            .collect();
+        let index = (0..)
+            .find(|index| !taken.contains(&find_stake_address(&pda, *index).0))
+            .unwrap_or_default();
+
+        let signature = self
+            .driver
+            .stake_from_vault(&pda, &owner, &vote_account, index, lamports)
+            .await?;
+        Ok((
+            Signature {
+                hash: signature.to_string(),
+            },
+            find_stake_address(&pda, index).0.to_string(),
+        ))
+    }
+
+    /// Deactivate a stake position of a custodial vault PDA, or withdraw it
+    /// back into the vault when already deactivated. Returns whether the stake
+    /// was withdrawn.
+    pub async fn unstake(
+        &self,
+        pda: &str,
+        owner: &str,
+        stake_account: &str,
+    ) -> Result<(Signature, bool)> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let stake_account = self.parse_pubkey(stake_account)?;
+
+        let (_, _, state) = self
+            .driver
+            .find_vault_stakes(&pda)
+            .await?
+            .into_iter()
+            .find(|(address, _, _)| *address == stake_account)
+            .ok_or_else(|| Error::NotFound {
+                message: format!("no stake account {stake_account} for vault {pda}"),
+            })?;
+
+        let active = state
+            .delegation()
+            .is_some_and(|delegation| delegation.deactivation_epoch == u64::MAX);
+        let signature = if active {
+            self.driver
+                .deactivate_vault_stake(&pda, &owner, &stake_account)
+                .await?
+        } else {
+            self.driver
+                .withdraw_vault_stake(&pda, &owner, &stake_account)
+                .await?
+        };
+        Ok((
+            Signature {
+                hash: signature.to_string(),
+            },
+            !active,
+        ))
+    }
+
+    /// List the stake positions of a vault PDA.
+    pub async fn list_stakes(&self, pda: &str) -> Result<Vec<StakePosition>> {
+        let pda = self.parse_pubkey(pda)?;
+        let stakes = self.driver.find_vault_stakes(&pda).await?;
+        Ok(stakes
+            .into_iter()
+            .map(|(address, lamports, state)| {
+                let delegation = match state {
+                    StakeStateV2::Stake(_, stake, _) => Some(stake.delegation),
+                    _ => None,
+                };
+                StakePosition {
+                    stake_account: address.to_string(),
+                    lamports,
+                    voter: delegation.map(|d| d.voter_pubkey.to_string()),
+                    activation_epoch: delegation.map(|d| d.activation_epoch),
+                    deactivation_epoch: delegation
+                        .map(|d| d.deactivation_epoch)
+                        .filter(|epoch| *epoch != u64::MAX),
+                }
+            })
+            .collect())
+    }
+
+    /// Make a custodial vault PDA co-owned, or replace its co-owners. The owner
+    /// is ignored for a vault that is already co-owned.
+    pub async fn set_owners(
+        &self,
+        pda: &str,
+        owner: &str,
+        co_owners: &[(String, u32)],
+        threshold: u32,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let (owner, _) = self.resolve_payee(&pda, owner).await?;
+        let (co_owners, threshold) = self.parse_co_owners(co_owners, threshold)?;
+        let signature = self
+            .driver
+            .set_vault_co_owners(&pda, &owner, co_owners, threshold)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Get the owners of a vault PDA, a single owner holding one share.
+    pub async fn get_owners(&self, pda: &str) -> Result<Owners> {
+        let pda = self.parse_pubkey(pda)?;
+        match self.driver.get_vault_co_owners(&pda).await? {
+            Some(co_owners) => Ok(Owners {
+                owners: co_owners.owners,
+                threshold: co_owners.threshold,
+            }),
+            None => {
+                let record = self.driver.get_vault_record(&pda).await?;
+                Ok(Owners {
+                    owners: vec![CoOwner {
+                        owner: record.owner,
+                        shares: 1,
+                    }],
+                    threshold: 1,
+                })
+            }
+        }
+    }
+
+    /// Transfer a co-owner's share of a co-owned vault PDA.
+    pub async fn transfer_share(
+        &self,
+        pda: &str,
+        co_owner: &str,
+        new_co_owner: &str,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let co_owner = self.parse_pubkey(co_owner)?;
+        let new_co_owner = self.parse_pubkey(new_co_owner)?;
+        let signature = self
+            .driver
+            .transfer_vault_share(&pda, &co_owner, &new_co_owner)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Set the beneficiary able to claim a custodial vault PDA after a period
+    /// of owner inactivity, or remove it.
+    pub async fn set_beneficiary(
+        &self,
+        pda: &str,
+        owner: &str,
+        beneficiary: Option<&str>,
+        inactivity_period: i64,
+    ) -> Result<Signature> {
         let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
         let owner = self.parse_pubkey(owner)?;
-        let signature = self.driver.close_vault(&pda, &owner).await?;
+        let inheritance = match beneficiary {
+            Some(beneficiary) => {
+                if inactivity_period <= 0 {
+                    return Err(Error::InvalidArgument {
+                        message: format!("invalid inactivity period: {inactivity_period}"),
+                    });
+                }
+                Some(Inheritance {
+                    beneficiary: self.parse_pubkey(beneficiary)?,
+                    inactivity_period,
+                })
+            }
+            None => None,
+        };
+        let signature = self
+            .driver
+            .set_vault_beneficiary(&pda, &owner, inheritance)
+            .await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Get the beneficiary of a vault PDA and the owner's last activity.
+    pub async fn get_beneficiary(&self, pda: &str) -> Result<BeneficiaryStatus> {
+        let pda = self.parse_pubkey(pda)?;
+        let record = self.driver.get_vault_record(&pda).await?;
+        Ok(BeneficiaryStatus {
+            inheritance: record.inheritance,
+            last_activity: record.last_activity,
+        })
+    }
+
+    /// Attach metadata to a custodial vault PDA, replacing any previous metadata.
+    pub async fn set_metadata(
+        &self,
+        pda: &str,
+        owner: &str,
+        metadata: VaultMetadata,
+    ) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let len = metadata.packed_len();
+        if len > VaultMetadata::MAX_LEN {
+            return Err(Error::InvalidArgument {
+                message: format!("metadata too large: {len} bytes"),
+            });
+        }
+        let signature = self
+            .driver
+            .set_vault_metadata(&pda, &owner, metadata)
+            .await?;
         Ok(Signature {
             hash: signature.to_string(),
         })
     }
+
+    /// Remove the metadata of a custodial vault PDA.
+    pub async fn clear_metadata(&self, pda: &str, owner: &str) -> Result<Signature> {
+        let pda = self.parse_pubkey(pda)?;
+        self.require_custodial(&pda).await?;
+        let owner = self.parse_pubkey(owner)?;
+        let signature = self.driver.clear_vault_metadata(&pda, &owner).await?;
+        Ok(Signature {
+            hash: signature.to_string(),
+        })
+    }
+
+    /// Move every vault of the current DART to a new intermediary, in batches,
+    /// reporting progress after each batch. Stops at the first failed batch;
+    /// running the job again picks up the vaults not yet rotated.
+    pub async fn rotate_intermediary(
+        &self,
+        new_dart: &str,
+        progress: Sender<Result<RotationProgress>>,
+    ) {
+        if let Err(err) = self.rotate_intermediary_batches(new_dart, &progress).await {
+            let _ = progress.send(Err(err)).await;
+        }
+    }
+
+    /// Rotate vault batches, sending progress after each one. The job runs to
+    /// completion even if nobody is listening for progress any more.
+    async fn rotate_intermediary_batches(
+        &self,
+        new_dart: &str,
+        progress: &Sender<Result<RotationProgress>>,
+    ) -> Result<()> {
+        let new_dart = self.parse_pubkey(new_dart)?;
+        let pdas = self.driver.find_vaults().await?;
+        let total = pdas.len();
+        let mut rotated = 0;
+        for batch in pdas.chunks(ROTATION_BATCH_SIZE) {
+            let signature = self
+                .driver
+                .rotate_vault_intermediary(batch, &new_dart)
+                .await?;
+            rotated += batch.len();
+            let report = RotationProgress {
+                signature: Signature {
+                    hash: signature.to_string(),
+                },
+                rotated,
+                total,
+            };
+            let _ = progress.send(Ok(report)).await;
+        }
+        Ok(())
+    }
 }
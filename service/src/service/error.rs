@@ -1,11 +1,18 @@
 use crate::driver::Error as DriverError;
 use solana_sdk::pubkey::ParsePubkeyError;
+use vault::error::VaultError;
 
 /// Service level errors.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("invalid argument: {message}")]
     InvalidArgument { message: String },
+    #[error("failed precondition: {message}")]
+    FailedPrecondition { message: String },
+    #[error("permission denied: {message}")]
+    PermissionDenied { message: String },
+    #[error("not found: {message}")]
+    NotFound { message: String },
     #[error("internal error: {message}")]
     InternalError { message: String },
 }
@@ -13,8 +20,58 @@ pub enum Error {
 /// Convert a core driver error into a service level driver error.
 impl From<DriverError> for Error {
     fn from(error: DriverError) -> Self {
-        Error::InternalError {
-            message: error.to_string(),
+        match error {
+            DriverError::ProgramError(vault_error) => vault_error.into(),
+            _ => Error::InternalError {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+/// Convert a vault program error into a service level error.
+impl From<VaultError> for Error {
+    fn from(error: VaultError) -> Self {
+        let message = error.to_string();
+        match error {
+            VaultError::IncorrectAccountKey
+            | VaultError::UnknownIntermediary
+            | VaultError::WrongOwner
+            | VaultError::MissingSignature
+            | VaultError::DestinationNotAllowed
+            | VaultError::WrongRecipient
+            | VaultError::InvalidApproval => Error::PermissionDenied { message },
+            VaultError::VaultClosed
+            | VaultError::NotInitialized
+            | VaultError::InvalidAccountOwner => Error::NotFound { message },
+            VaultError::InsufficientFunds
+            | VaultError::NoPendingOwner
+            | VaultError::OutdatedRecord
+            | VaultError::Frozen
+            | VaultError::Locked
+            | VaultError::AllowanceExceeded
+            | VaultError::AllowanceExpired
+            | VaultError::WithdrawalLimitExceeded
+            | VaultError::DestinationCoolingOff
+            | VaultError::AlreadyInitialized
+            | VaultError::StaleApproval
+            | VaultError::NoPendingRecovery
+            | VaultError::RecoveryNotReady
+            | VaultError::RecoveryPending
+            | VaultError::OpenAccounts
+            | VaultError::NoBeneficiary
+            | VaultError::OwnerNotInactive
+            | VaultError::OwnerChanged
+            | VaultError::NotProgramAddress => Error::FailedPrecondition { message },
+            VaultError::Overflow
+            | VaultError::InvalidFeeSchedule
+            | VaultError::InvalidLock
+            | VaultError::InvalidLimit
+            | VaultError::InvalidMultisig
+            | VaultError::InvalidCoOwners
+            | VaultError::InvalidInactivityPeriod
+            | VaultError::MetadataTooLarge
+            | VaultError::TooManyIntermediaries => Error::InvalidArgument { message },
         }
     }
 }
@@ -1,7 +1,10 @@
-use solana_sdk::pubkey::Pubkey;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{pubkey::Pubkey, transaction::Transaction};
 use std::str::FromStr;
+use vault::state::{AuthorityMode, CoOwner, CoOwners};
 
 use super::{Error, Result, Service};
+use crate::entity::Approval;
 
 impl Service {
     /// Parse a public key from a string.
@@ -21,4 +24,87 @@ impl Service {
             Ok(String::from(seed))
         }
     }
+
+    /// Ensure a lamport amount is non-zero.
+    pub(crate) fn validate_lamports(&self, lamports: u64) -> Result<u64> {
+        if lamports == 0 {
+            Err(Error::InvalidArgument {
+                message: "lamports must be greater than zero".to_string(),
+            })
+        } else {
+            Ok(lamports)
+        }
+    }
+
+    /// Parse an owner approval, ensuring the signature is ed25519 sized.
+    pub(crate) fn parse_approval(&self, nonce: u64, signature: &[u8]) -> Result<Approval> {
+        let signature = signature.try_into().map_err(|_| Error::InvalidArgument {
+            message: format!("invalid approval signature length: {}", signature.len()),
+        })?;
+        Ok(Approval { nonce, signature })
+    }
+
+    /// Parse a co-owner list with its approval threshold.
+    pub(crate) fn parse_co_owners(
+        &self,
+        co_owners: &[(String, u32)],
+        threshold: u32,
+    ) -> Result<(Vec<CoOwner>, u8)> {
+        if co_owners.is_empty() || co_owners.len() > CoOwners::MAX_OWNERS {
+            return Err(Error::InvalidArgument {
+                message: format!("invalid number of co-owners: {}", co_owners.len()),
+            });
+        }
+        let threshold = u8::try_from(threshold)
+            .ok()
+            .filter(|threshold| (1..=co_owners.len()).contains(&usize::from(*threshold)))
+            .ok_or_else(|| Error::InvalidArgument {
+                message: format!("invalid co-owner threshold: {threshold}"),
+            })?;
+        let co_owners = co_owners
+            .iter()
+            .map(|(owner, shares)| {
+                Ok(CoOwner {
+                    owner: self.parse_pubkey(owner)?,
+                    shares: *shares,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((co_owners, threshold))
+    }
+
+    /// Resolve who a vault pays out to: the given owner, or for a co-owned
+    /// vault its co-owner list together with the co-owner wallets.
+    pub(crate) async fn resolve_payee(
+        &self,
+        pda: &Pubkey,
+        owner: &str,
+    ) -> Result<(Pubkey, Vec<Pubkey>)> {
+        match self.driver.get_vault_co_owners(pda).await? {
+            Some(co_owners) => Ok((
+                self.driver.get_vault_record(pda).await?.owner,
+                co_owners.owners.iter().map(|c| c.owner).collect(),
+            )),
+            None => Ok((self.parse_pubkey(owner)?, vec![])),
+        }
+    }
+
+    /// Encode a transaction in base64 wire format.
+    pub(crate) fn encode_transaction(&self, transaction: &Transaction) -> Result<String> {
+        let bytes = bincode::serialize(transaction).map_err(|err| Error::InternalError {
+            message: err.to_string(),
+        })?;
+        Ok(STANDARD.encode(bytes))
+    }
+
+    /// Ensure the DART can sign for a vault on its own.
+    pub(crate) async fn require_custodial(&self, pda: &Pubkey) -> Result<()> {
+        let record = self.driver.get_vault_record(pda).await?;
+        if record.authority_mode != AuthorityMode::Custodial {
+            return Err(Error::FailedPrecondition {
+                message: format!("vault requires owner signature: {pda}"),
+            });
+        }
+        Ok(())
+    }
 }
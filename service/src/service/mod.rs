@@ -11,6 +11,7 @@ mod helpers;
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Service encapsulates the business logic for fauxstodian.
+#[derive(Clone)]
 pub struct Service {
     driver: Arc<Box<dyn SolanaDriver>>,
 }